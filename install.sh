@@ -25,6 +25,8 @@ function main() {
     "./build.sh" && \
     copy_config_file "nvim-config.lua" && \
     copy_config_file "status.toml" && \
+    copy_config_file "config.toml" && \
+    copy_config_file "templates.toml" && \
     sudo ln -sf "$(pwd)/target/release/stmux" /usr/local/bin/stmux && \
     tmux display-message "#[fg=#8a60ab,align=centre]stmux #[fg=#e0e0e0]installed"
 }
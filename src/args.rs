@@ -1,4 +1,10 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum, builder::NonEmptyStringValueParser};
+
+use crate::{
+    model::TemplateVariable,
+    template,
+    utils::{self, PathRewrite},
+};
 
 #[derive(Parser, Debug)]
 #[command(long_about = None, disable_help_flag = false, disable_help_subcommand = true)]
@@ -7,12 +13,21 @@ pub struct Args {
     pub(crate) action: Action,
 }
 
+/// The `--format` of list commands, given after the command, e.g. `sessions list --format json`.
+#[derive(clap::Args, Debug)]
+pub(crate) struct ListOutput {
+    /// Write the list to stdout, as plain text or JSON.
+    #[arg(long)]
+    pub(crate) format: Option<OutputFormat>,
+}
+
 #[derive(Subcommand, Debug)]
 #[warn(clippy::enum_variant_names)]
 pub(crate) enum ConfigPrintFilename {
     Sessions,
     RecentSessions,
     Bookmarks,
+    Templates,
 }
 
 #[derive(Subcommand, Debug)]
@@ -20,6 +35,8 @@ pub(crate) enum ConfigAction {
     Print {
         #[command(subcommand)]
         action: ConfigPrintFilename,
+        #[command(flatten)]
+        output: ListOutput,
     },
 }
 
@@ -34,14 +51,81 @@ pub(crate) enum SessionAction {
     Select {
         /// Session name to select or create (if configured).
         session_name: String,
+        /// Print the tmux commands instead of running them.
+        #[arg(long = "dry-run")]
+        dry_run: bool,
     },
     /// Save the current session.
-    Save,
+    Save {
+        /// Also store the contents of every pane.
+        #[arg(long)]
+        scrollback: bool,
+    },
     /// Delete a session.
     Delete {
         /// Session name to delete. session_name: String,
         session_name: String,
     },
+    /// Kill a session and switch to the next recent one.
+    Kill {
+        /// Session name to kill, the current session by default.
+        session_name: Option<String>,
+        /// Save the session before killing it.
+        #[arg(long, conflicts_with = "forget")]
+        save: bool,
+        /// Delete the stored session too, also when it is not running.
+        #[arg(long)]
+        forget: bool,
+    },
+    /// Rename a session, live and stored, in the recent sessions and the bookmarks too.
+    Rename {
+        /// Current session name.
+        session_name: String,
+        /// New session name.
+        new_name: String,
+    },
+    /// Store a copy of a session under another name.
+    Clone {
+        /// Stored or live session to copy.
+        session_name: String,
+        /// Name of the copy.
+        new_name: String,
+        /// Replace a directory at the start of the pane paths, can be repeated.
+        #[arg(long = "path-rewrite", value_name = "FROM=TO", value_parser = utils::parse_path_rewrite)]
+        path_rewrites: Vec<PathRewrite>,
+        /// Start the copy and switch to it.
+        #[arg(long)]
+        start: bool,
+    },
+    /// Create a session from a template.
+    New {
+        /// Template name in the templates file.
+        template: String,
+        /// Session name, also available as `${name}` in the template. Characters tmux doesn't
+        /// allow are replaced.
+        #[arg(long)]
+        name: String,
+        /// Project path available as `${path}`, the current directory by default.
+        #[arg(long)]
+        path: Option<String>,
+        /// More template variables.
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = template::parse_variable)]
+        variables: Vec<TemplateVariable>,
+        /// Start the session and switch to it.
+        #[arg(long)]
+        start: bool,
+    },
+    /// Create and store a session for a project directory, unless there is one, and switch to it.
+    OpenProject {
+        /// Project directory, `~` is expanded. The directory name becomes the session name,
+        /// prefixed with the parent directory name if a session of another directory has it.
+        path: String,
+    },
+    /// Create the session again from the template it was created from.
+    ApplyTemplate {
+        /// Session name to update.
+        session_name: String,
+    },
     /// Update session settings.
     Update {
         /// Session name to update.
@@ -61,21 +145,82 @@ pub(crate) enum SessionsAction {
     Save {
         /// Optional filename to store sessions.
         filename: Option<String>,
+        /// Also store the contents of every pane.
+        #[arg(long)]
+        scrollback: bool,
     },
     /// Restore all sessions managed by stmux.
     Restore {
         /// Filename to restore sessions from.
         filename: Option<String>,
+        /// Print the tmux commands instead of running them.
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+    },
+    /// Periodically snapshot all live sessions into the snapshots directory.
+    Autosave {
+        /// Seconds between snapshots.
+        #[arg(long)]
+        interval: Option<u64>,
+        /// Number of snapshots to keep.
+        #[arg(long)]
+        keep: Option<usize>,
+        /// Take a single snapshot and exit (e.g. from a tmux hook).
+        #[arg(long)]
+        once: bool,
     },
     /// List sessions managed by stmux.
-    List,
+    List {
+        #[command(flatten)]
+        output: ListOutput,
+    },
+    /// List previous versions of the sessions file.
+    History,
+    /// Restore a previous version of the sessions file.
+    Rollback {
+        /// Version number as shown by `sessions history`.
+        version: usize,
+    },
+    /// Check the saved window layouts.
+    Check {
+        /// Store layouts with a corrected checksum.
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Add sessions converted from a tmuxinator or tmuxp project file, or a tmux-resurrect save
+    /// file, to the stored sessions.
+    Import {
+        /// Project file format.
+        #[arg(long = "from")]
+        format: ImportFormat,
+        /// Project file (YAML) or resurrect save file.
+        filename: String,
+    },
+    /// Compare stored sessions with the live ones.
+    Diff {
+        /// Session to compare, all stored and live sessions by default.
+        session_name: Option<String>,
+        #[command(flatten)]
+        output: ListOutput,
+    },
+    /// Print a script recreating a stored session without stmux.
+    Export {
+        /// Script format.
+        #[arg(long, default_value = "sh")]
+        format: ExportFormat,
+        /// Session name to export.
+        session_name: String,
+    },
     /// Convert old toml config to a new format (to be removed).
     Convert { filename: String },
 }
 
 #[derive(Subcommand, Debug)]
 pub(crate) enum RecentSessionAction {
-    List,
+    List {
+        #[command(flatten)]
+        output: ListOutput,
+    },
     Next,
     Previous,
     Edit,
@@ -85,18 +230,60 @@ pub(crate) enum RecentSessionAction {
 #[derive(Subcommand, Debug)]
 pub(crate) enum BookmarkAction {
     /// List all bookmarked sessions.
-    List,
+    List {
+        #[command(flatten)]
+        output: ListOutput,
+    },
     /// Bookmark the current session.
     Set,
     /// Switch to or create a bookmarked session.
     Select {
-        /// Index of bookmarked session to select.
-        index: usize,
+        /// Slot of the bookmarked session to select.
+        slot: usize,
         #[arg(long = "smart-focus")]
         smart_focus: Option<usize>,
     },
     /// Edit bookmarks.
     Edit,
+    /// Switch between bookmark sets.
+    Group {
+        #[command(subcommand)]
+        action: BookmarkGroupAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum BookmarkGroupAction {
+    /// List bookmark groups, the active one is marked.
+    List {
+        #[command(flatten)]
+        output: ListOutput,
+    },
+    /// Use the bookmarks of the group, creating it if it doesn't exist.
+    Use {
+        /// Group name, `default` for the top level bookmarks.
+        #[arg(value_parser = NonEmptyStringValueParser::new())]
+        name: String,
+    },
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub(crate) enum ImportFormat {
+    Tmuxinator,
+    Tmuxp,
+    Resurrect,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub(crate) enum OutputFormat {
+    Plain,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub(crate) enum ExportFormat {
+    /// A bash script running plain tmux commands.
+    Sh,
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -111,6 +298,9 @@ pub(crate) enum WindowAction {
         #[arg(value_enum)]
         split_type: SplitType,
         session_name: String,
+        /// Print the tmux commands instead of running them.
+        #[arg(long = "dry-run")]
+        dry_run: bool,
     },
 }
 
@@ -0,0 +1,79 @@
+use std::{thread::sleep, time::Duration};
+
+use crate::{
+    error::{Error, Result},
+    session::{Session, SessionImpl},
+    sessions::SessionStorage,
+    settings::SaveSettings,
+    snapshots::Snapshots,
+    tmux::Tmux,
+    utils,
+};
+
+pub(crate) trait Autosave {
+    /// Writes a single snapshot of all live sessions. Returns `false` if there was nothing to
+    /// save, e.g. because the tmux server is not running.
+    fn snapshot(&self, keep: usize) -> Result<bool>;
+    /// Takes a snapshot every `interval` until the tmux server goes away.
+    fn run(&self, interval: Duration, keep: usize) -> Result<()>;
+}
+
+pub(crate) struct AutosaveImpl<'t, 's, 'n, 'c, T: Tmux> {
+    tmux: &'t T,
+    sessions: &'s dyn SessionStorage,
+    snapshots: &'n dyn Snapshots,
+    settings: &'c SaveSettings,
+}
+
+impl<'t, 's, 'n, 'c, T: Tmux> AutosaveImpl<'t, 's, 'n, 'c, T> {
+    pub(crate) fn new(
+        tmux: &'t T,
+        sessions: &'s dyn SessionStorage,
+        snapshots: &'n dyn Snapshots,
+        settings: &'c SaveSettings,
+    ) -> Self {
+        Self {
+            tmux,
+            sessions,
+            snapshots,
+            settings,
+        }
+    }
+}
+
+impl<'t, 's, 'n, 'c, T: Tmux> Autosave for AutosaveImpl<'t, 's, 'n, 'c, T> {
+    fn snapshot(&self, keep: usize) -> Result<bool> {
+        let session = SessionImpl::new(self.tmux);
+        let live_sessions = session.list(self.settings)?;
+
+        if live_sessions.is_empty() {
+            return Ok(false);
+        }
+
+        // Unlike `sessions save`, the live layout wins here, but hand-written fields survive.
+        let mut stored_sessions = self.sessions.load()?;
+        let live_sessions = live_sessions
+            .into_iter()
+            .map(|(name, live)| {
+                let stored = stored_sessions.remove(&name);
+                (name, utils::merge_session(stored, live))
+            })
+            .collect();
+        let snapshot = utils::merge(live_sessions, stored_sessions);
+        let toml_string =
+            toml::to_string(&snapshot).map_err(|error| Error::parse("snapshot", error))?;
+
+        self.snapshots.write(&toml_string)?;
+        self.snapshots.prune(keep);
+        Ok(true)
+    }
+
+    fn run(&self, interval: Duration, keep: usize) -> Result<()> {
+        while self.snapshot(keep)? {
+            sleep(interval);
+        }
+
+        eprintln!("No tmux sessions to save, autosave stopped.");
+        Ok(())
+    }
+}
@@ -0,0 +1,406 @@
+use std::{collections::BTreeMap, fs, io::ErrorKind, path::Path};
+
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    model::SessionName,
+    state_file,
+};
+
+/// A session selected by its slot number. Slots don't have to be contiguous, the gaps are empty
+/// slots.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct Bookmark {
+    pub(crate) slot: usize,
+    pub(crate) session: SessionName,
+    /// Shown in the status line instead of the session name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) alias: Option<String>,
+}
+
+impl Bookmark {
+    pub(crate) fn label(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.session)
+    }
+}
+
+/// The group of the top level bookmarks, active unless another one is used.
+pub(crate) const DEFAULT_GROUP: &str = "default";
+
+#[derive(Deserialize, Default)]
+struct BookmarkList {
+    /// The active group.
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default)]
+    bookmarks: Vec<Bookmark>,
+    #[serde(default)]
+    groups: BTreeMap<String, BookmarkGroup>,
+}
+
+#[derive(Deserialize, Default)]
+struct BookmarkGroup {
+    #[serde(default)]
+    bookmarks: Vec<Bookmark>,
+}
+
+impl BookmarkList {
+    fn active_group(&self) -> &str {
+        self.group.as_deref().unwrap_or(DEFAULT_GROUP)
+    }
+
+    fn active_bookmarks(&self) -> Vec<Bookmark> {
+        match self.groups.get(self.active_group()) {
+            Some(group) => group.bookmarks.clone(),
+            None if self.active_group() == DEFAULT_GROUP => self.bookmarks.clone(),
+            None => vec![],
+        }
+    }
+
+    fn active_bookmarks_mut(&mut self) -> &mut Vec<Bookmark> {
+        match self.group.clone() {
+            Some(group) if group != DEFAULT_GROUP => {
+                &mut self.groups.entry(group).or_default().bookmarks
+            }
+            _ => &mut self.bookmarks,
+        }
+    }
+
+    fn all_bookmarks_mut(&mut self) -> impl Iterator<Item = &mut Vec<Bookmark>> {
+        std::iter::once(&mut self.bookmarks)
+            .chain(self.groups.values_mut().map(|group| &mut group.bookmarks))
+    }
+}
+
+#[automock]
+pub(crate) trait BookmarkFile {
+    /// Bookmarks of the active group sorted by slot.
+    fn read(&self) -> Result<Vec<Bookmark>>;
+    /// Bookmarks the session in the active group, in the slot after the last one, unless it's
+    /// bookmarked already. Returns whether it was added.
+    fn append(&self, session_name: &str) -> Result<bool>;
+    /// Renames the session in every group, keeping its slot and alias.
+    fn rename(&self, session_name: &str, new_name: &str) -> Result<()>;
+    fn active_group(&self) -> Result<String>;
+    /// The active group and its bookmarks sorted by slot, from a single read of the file.
+    fn read_group(&self) -> Result<(String, Vec<Bookmark>)>;
+    /// Group names, the default group first.
+    fn groups(&self) -> Result<Vec<String>>;
+    /// Makes the group active, creating it if it doesn't exist.
+    fn use_group(&self, group: &str) -> Result<()>;
+    /// Writes the file, with the migrated plain bookmarks, unless it exists.
+    fn create(&self) -> Result<()>;
+    /// The bookmarks of every group, as they're written to the file.
+    fn content(&self) -> Result<String>;
+}
+
+pub(crate) struct BookmarkFileImpl {
+    filename: String,
+}
+
+impl BookmarkFileImpl {
+    pub(crate) fn new(filename: &str) -> Self {
+        Self {
+            filename: filename.to_string(),
+        }
+    }
+
+    /// The file bookmarks were kept in before, one name per line, numbered by position.
+    fn plain_filename(&self) -> String {
+        Path::new(&self.filename)
+            .with_extension("")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn parse(&self, content: &str) -> Result<BookmarkList> {
+        let mut list = toml::from_str::<BookmarkList>(content)
+            .map_err(|error| Error::parse(&self.filename, error.message()))?;
+
+        if list.groups.contains_key(DEFAULT_GROUP) {
+            return Err(Error::parse(
+                &self.filename,
+                format!("The top level bookmarks are the '{}' group.", DEFAULT_GROUP),
+            ));
+        }
+
+        for bookmarks in list.all_bookmarks_mut() {
+            bookmarks.sort_by_key(|bookmark| bookmark.slot);
+
+            if let Some(pair) = bookmarks
+                .windows(2)
+                .find(|pair| pair[0].slot == pair[1].slot)
+            {
+                return Err(Error::parse(
+                    &self.filename,
+                    format!("Slot {} is used more than once.", pair[0].slot),
+                ));
+            }
+        }
+
+        Ok(list)
+    }
+
+    /// Numbers the names of the plain file by their position.
+    fn migrate(&self) -> Result<BookmarkList> {
+        let plain_filename = self.plain_filename();
+        let content = match fs::read_to_string(&plain_filename) {
+            Ok(content) => content,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                return Ok(BookmarkList::default());
+            }
+            Err(error) => return Err(Error::io(&plain_filename)(error)),
+        };
+
+        let bookmarks = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .enumerate()
+            .map(|(i, name)| Bookmark {
+                slot: i + 1,
+                session: name.to_string(),
+                alias: None,
+            })
+            .collect();
+
+        Ok(BookmarkList {
+            bookmarks,
+            ..BookmarkList::default()
+        })
+    }
+
+    fn load(&self) -> Result<BookmarkList> {
+        match fs::read_to_string(&self.filename) {
+            Ok(content) => self.parse(&content),
+            Err(error) if error.kind() == ErrorKind::NotFound => self.migrate(),
+            Err(error) => Err(Error::io(&self.filename)(error)),
+        }
+    }
+
+    /// Reads, transforms and writes the bookmarks while holding the file lock.
+    fn modify<F>(&self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut BookmarkList),
+    {
+        let _lock = state_file::lock(&self.filename)?;
+        let mut list = self.load()?;
+        f(&mut list);
+
+        for bookmarks in list.all_bookmarks_mut() {
+            bookmarks.sort_by_key(|bookmark| bookmark.slot);
+        }
+
+        state_file::write(&self.filename, &to_toml(&list))
+    }
+}
+
+impl BookmarkFile for BookmarkFileImpl {
+    fn read(&self) -> Result<Vec<Bookmark>> {
+        Ok(self.load()?.active_bookmarks())
+    }
+
+    fn append(&self, session_name: &str) -> Result<bool> {
+        let mut added = false;
+
+        self.modify(|list| {
+            let bookmarks = list.active_bookmarks_mut();
+
+            if !bookmarks.iter().any(|b| b.session == session_name) {
+                bookmarks.push(Bookmark {
+                    slot: bookmarks.iter().map(|b| b.slot).max().unwrap_or(0) + 1,
+                    session: session_name.to_string(),
+                    alias: None,
+                });
+                added = true;
+            }
+        })?;
+
+        Ok(added)
+    }
+
+    fn rename(&self, session_name: &str, new_name: &str) -> Result<()> {
+        self.modify(|list| {
+            for bookmark in list
+                .all_bookmarks_mut()
+                .flat_map(|bookmarks| bookmarks.iter_mut())
+            {
+                if bookmark.session == session_name {
+                    bookmark.session = new_name.to_string();
+                }
+            }
+        })
+    }
+
+    fn active_group(&self) -> Result<String> {
+        Ok(self.load()?.active_group().to_string())
+    }
+
+    fn read_group(&self) -> Result<(String, Vec<Bookmark>)> {
+        let list = self.load()?;
+
+        Ok((list.active_group().to_string(), list.active_bookmarks()))
+    }
+
+    fn groups(&self) -> Result<Vec<String>> {
+        Ok(std::iter::once(DEFAULT_GROUP.to_string())
+            .chain(self.load()?.groups.into_keys())
+            .collect())
+    }
+
+    fn use_group(&self, group: &str) -> Result<()> {
+        self.modify(|list| {
+            list.group = Some(group.to_string()).filter(|group| group != DEFAULT_GROUP);
+            list.active_bookmarks_mut();
+        })
+    }
+
+    fn create(&self) -> Result<()> {
+        if Path::new(&self.filename).exists() {
+            return Ok(());
+        }
+
+        self.modify(|_| ())
+    }
+
+    fn content(&self) -> Result<String> {
+        Ok(to_toml(&self.load()?))
+    }
+}
+
+fn to_toml(list: &BookmarkList) -> String {
+    let mut content = match &list.group {
+        Some(group) => format!("group = {}\n", toml::Value::String(group.clone())),
+        None => String::new(),
+    };
+    content.push_str(&bookmarks_to_toml(&list.bookmarks));
+
+    for (name, group) in &list.groups {
+        let is_bare_key = name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        let key = if is_bare_key {
+            name.clone()
+        } else {
+            toml::Value::String(name.clone()).to_string()
+        };
+
+        content.push_str(&format!(
+            "\n[groups.{}]\n{}",
+            key,
+            bookmarks_to_toml(&group.bookmarks)
+        ));
+    }
+
+    content
+}
+
+/// One inline table per line, so that the file is easy to edit in the popup.
+fn bookmarks_to_toml(bookmarks: &[Bookmark]) -> String {
+    let lines = bookmarks
+        .iter()
+        .map(|bookmark| {
+            let alias = match &bookmark.alias {
+                Some(alias) => format!(", alias = {}", toml::Value::String(alias.clone())),
+                None => String::new(),
+            };
+
+            format!(
+                "  {{ slot = {}, session = {}{} }},\n",
+                bookmark.slot,
+                toml::Value::String(bookmark.session.clone()),
+                alias
+            )
+        })
+        .collect::<String>();
+
+    format!("bookmarks = [\n{}]\n", lines)
+}
+
+#[cfg(test)]
+mod bookmark_file_tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+
+    #[test]
+    fn should_migrate_the_plain_file() {
+        // Given
+        let directory = TempDir::new();
+        fs::write(directory.filename("bookmarks"), "main\n\nwork\n").unwrap();
+        let file = BookmarkFileImpl::new(&directory.filename("bookmarks.toml"));
+
+        // When
+        file.append("notes").unwrap();
+
+        // Then
+        let content = fs::read_to_string(directory.filename("bookmarks.toml")).unwrap();
+        assert_eq!(
+            content,
+            r#"bookmarks = [
+  { slot = 1, session = "main" },
+  { slot = 2, session = "work" },
+  { slot = 3, session = "notes" },
+]
+"#
+        );
+    }
+
+    #[test]
+    fn should_bookmark_in_the_active_group() {
+        // Given
+        let directory = TempDir::new();
+        let filename = directory.filename("bookmarks.toml");
+        fs::write(
+            &filename,
+            "bookmarks = [\n  { slot = 1, session = \"main\" },\n]\n",
+        )
+        .unwrap();
+        let file = BookmarkFileImpl::new(&filename);
+
+        // When
+        file.use_group("on-call").unwrap();
+        file.append("alerts").unwrap();
+        file.rename("main", "work").unwrap();
+
+        // Then
+        let content = fs::read_to_string(&filename).unwrap();
+        assert_eq!(
+            content,
+            r#"group = "on-call"
+bookmarks = [
+  { slot = 1, session = "work" },
+]
+
+[groups.on-call]
+bookmarks = [
+  { slot = 1, session = "alerts" },
+]
+"#
+        );
+    }
+
+    #[test]
+    fn should_keep_empty_slots_and_aliases() {
+        // Given
+        let file = BookmarkFileImpl::new("bookmarks.toml");
+
+        // When
+        let result = file.parse(
+            r#"bookmarks = [
+  { slot = 4, session = "work", alias = "w" },
+  { slot = 1, session = "main" },
+]"#,
+        );
+
+        // Then
+        let labels = result
+            .unwrap()
+            .bookmarks
+            .iter()
+            .map(|bookmark| (bookmark.slot, bookmark.label().to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec![(1, "main".to_string()), (4, "w".to_string())]);
+    }
+}
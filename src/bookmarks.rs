@@ -1,79 +1,192 @@
 use std::cmp::max;
 
+use serde::Serialize;
+
 use crate::{
-    config::Config, model::WindowDimension, session_name_file::SessionNameFile, tmux::Tmux,
+    args::OutputFormat, bookmark_file::BookmarkFile, config::Config, error::Result,
+    model::WindowDimension, output, settings::Settings, tmux::Tmux,
 };
 
 pub(crate) trait Bookmarks {
-    fn print(&self);
-    fn set(&self, tmux: &dyn Tmux) -> bool;
-    fn select(&self, index: usize) -> Option<String>;
-    fn edit(&self, config: &dyn Config, tmux: &dyn Tmux);
+    fn print(&self, format: Option<&OutputFormat>) -> Result<()>;
+    fn set(&self, tmux: &dyn Tmux) -> Result<bool>;
+    /// The session bookmarked in the slot, `None` for an empty slot.
+    fn select(&self, slot: usize) -> Result<Option<String>>;
+    fn edit(&self, config: &dyn Config, settings: &Settings, tmux: &dyn Tmux) -> Result<()>;
+    fn print_groups(&self, format: Option<&OutputFormat>) -> Result<()>;
+    /// Makes the group the one `select` and the status line use.
+    fn use_group(&self, group: &str) -> Result<()>;
+}
+
+#[derive(Serialize)]
+struct BookmarkGroup {
+    name: String,
+    active: bool,
 }
 
-pub(crate) struct BookmarksImpl<'s, S: SessionNameFile> {
+pub(crate) struct BookmarksImpl<'s, S: BookmarkFile> {
     bookmarks_file: &'s S,
 }
 
-impl<'s, S: SessionNameFile> BookmarksImpl<'s, S> {
+impl<'s, S: BookmarkFile> BookmarksImpl<'s, S> {
     pub(crate) fn new(bookmarks_file: &'s S) -> Self {
         Self { bookmarks_file }
     }
 }
 
-impl<'s, S: SessionNameFile> Bookmarks for BookmarksImpl<'s, S> {
-    fn print(&self) {
-        let bookmarks = self.bookmarks_file.read();
+impl<'s, S: BookmarkFile> Bookmarks for BookmarksImpl<'s, S> {
+    fn print(&self, format: Option<&OutputFormat>) -> Result<()> {
+        let bookmarks = self.bookmarks_file.read()?;
+        let lines = bookmarks
+            .iter()
+            .map(|bookmark| match &bookmark.alias {
+                Some(alias) => format!("{:3}: {} ({})", bookmark.slot, bookmark.session, alias),
+                None => format!("{:3}: {}", bookmark.slot, bookmark.session),
+            })
+            .collect::<Vec<_>>();
 
-        for (i, bookmark) in bookmarks.iter().enumerate() {
-            eprintln!("{:3}: {}", i + 1, bookmark);
-        }
+        output::print(format, &bookmarks, &lines)
     }
 
-    fn set(&self, tmux: &dyn Tmux) -> bool {
-        let current_session_name = tmux.current_session_name();
-        let bookmarks = self.bookmarks_file.read();
+    fn print_groups(&self, format: Option<&OutputFormat>) -> Result<()> {
+        let active_group = self.bookmarks_file.active_group()?;
+        let groups = self
+            .bookmarks_file
+            .groups()?
+            .into_iter()
+            .map(|name| BookmarkGroup {
+                active: name == active_group,
+                name,
+            })
+            .collect::<Vec<_>>();
+        let lines = groups
+            .iter()
+            .map(|group| match group.active {
+                true => format!("* {}", group.name),
+                false => format!("  {}", group.name),
+            })
+            .collect::<Vec<_>>();
 
-        if !bookmarks.contains(&current_session_name) {
-            self.bookmarks_file.append(&current_session_name);
-            true
-        } else {
-            false
-        }
+        output::print(format, &groups, &lines)
     }
 
-    fn select(&self, index: usize) -> Option<String> {
-        let bookmarks = self.bookmarks_file.read();
-        bookmarks.get(index - 1).map(|s| s.to_string())
+    fn use_group(&self, group: &str) -> Result<()> {
+        self.bookmarks_file.use_group(group)
     }
 
-    fn edit(&self, config: &dyn Config, tmux: &dyn Tmux) {
-        let width = self
-            .bookmarks_file
-            .read()
-            .iter()
-            .map(|s| s.len())
+    fn set(&self, tmux: &dyn Tmux) -> Result<bool> {
+        let current_session_name = tmux.current_session_name()?;
+        self.bookmarks_file.append(&current_session_name)
+    }
+
+    fn select(&self, slot: usize) -> Result<Option<String>> {
+        let bookmarks = self.bookmarks_file.read()?;
+
+        Ok(bookmarks
+            .into_iter()
+            .find(|bookmark| bookmark.slot == slot)
+            .map(|bookmark| bookmark.session))
+    }
+
+    fn edit(&self, config: &dyn Config, settings: &Settings, tmux: &dyn Tmux) -> Result<()> {
+        self.bookmarks_file.create()?;
+        // The editor shows the whole file, every group included.
+        let content = self.bookmarks_file.content()?;
+        let width = content
+            .lines()
+            .map(|line| line.chars().count())
             .max()
-            .unwrap_or(17);
+            .unwrap_or(0);
 
         let popup_width = max(width + 6, 17);
-        let popup_height = 7;
-        let y = tmux.window_dimension().map(|d| d.height / 2 - 1);
+        let popup_height = max(content.lines().count() + 2, 7);
+        let y = tmux.window_dimension()?.map(|d| d.height / 2 - 1);
 
         tmux.display_popup(
             "Bookmarks",
-            &Some("fg=#9f7fff,italics,align=centre".to_string()),
-            "fg=#806aba",
+            &Some(settings.popup.title_style.clone()),
+            &settings.popup.style,
             &WindowDimension {
                 width: popup_width,
                 height: popup_height,
             },
             &y,
-            &format!(
-                "nvim --clean -u {} {}",
-                config.neovim_config_filename(),
-                config.bookmarks_filename()
+            &settings.editor_command(
+                &config.neovim_config_filename(),
+                &config.bookmarks_filename(),
             ),
-        );
+        )
+    }
+}
+
+#[cfg(test)]
+mod bookmarks_tests {
+    use super::*;
+    use crate::{
+        bookmark_file::{Bookmark, MockBookmarkFile},
+        config::MockConfig,
+        tmux::MockTmux,
+    };
+
+    fn bookmark(slot: usize, session: &str) -> Bookmark {
+        Bookmark {
+            slot,
+            session: session.to_string(),
+            alias: None,
+        }
+    }
+
+    #[test]
+    fn should_select_by_slot_across_gaps() {
+        // Given
+        let mut file = MockBookmarkFile::new();
+        file.expect_read()
+            .returning(|| Ok(vec![bookmark(1, "main"), bookmark(4, "work")]));
+        let bookmarks = BookmarksImpl::new(&file);
+
+        // When
+        let result = bookmarks.select(4);
+
+        // Then
+        assert_eq!(result.unwrap(), Some("work".to_string()));
+        assert_eq!(bookmarks.select(2).unwrap(), None);
+    }
+
+    #[test]
+    fn should_size_the_popup_to_the_whole_file() {
+        // Given
+        let mut file = MockBookmarkFile::new();
+        file.expect_create().returning(|| Ok(()));
+        file.expect_content().returning(|| {
+            Ok(r#"bookmarks = [
+  { slot = 1, session = "main" },
+]
+
+[groups.on-call]
+bookmarks = [
+  { slot = 1, session = "alerts", alias = "a" },
+]
+"#
+            .to_string())
+        });
+        let mut tmux = MockTmux::new();
+        tmux.expect_window_dimension().returning(|| Ok(None));
+        tmux.expect_display_popup()
+            .withf(|_, _, _, dimension, _, _| dimension.width == 54 && dimension.height == 10)
+            .times(1)
+            .returning(|_, _, _, _, _, _| Ok(()));
+        let mut config = MockConfig::new();
+        config
+            .expect_neovim_config_filename()
+            .returning(|| "init.lua".to_string());
+        config
+            .expect_bookmarks_filename()
+            .returning(|| "bookmarks.toml".to_string());
+
+        // When
+        let result = BookmarksImpl::new(&file).edit(&config, &Settings::default(), &tmux);
+
+        // Then
+        assert!(result.is_ok());
     }
 }
@@ -1,7 +1,24 @@
-use std::process::Command;
+use std::{
+    cell::{Cell, RefCell},
+    io,
+    os::unix::process::ExitStatusExt,
+    process::{Command, ExitStatus, Output},
+};
+
+use crate::utils::shell_quote;
 
 pub(crate) trait CommandBuilder {
     fn new_command(&self) -> Command;
+
+    /// Runs a command made by `new_command`. Every tmux command is run through this and `output`,
+    /// so that they can be written to a script or printed instead.
+    fn status(&self, command: &mut Command) -> io::Result<ExitStatus> {
+        command.status()
+    }
+
+    fn output(&self, command: &mut Command) -> io::Result<Output> {
+        command.output()
+    }
 }
 
 pub(crate) struct CommandBuilderImpl;
@@ -12,6 +29,173 @@ impl CommandBuilder for CommandBuilderImpl {
     }
 }
 
+/// Collects commands as shell script lines instead of running them. The output of a command,
+/// which is always a new pane id, is stored in a `pane_<n>` variable that later commands use.
+#[derive(Default)]
+pub(crate) struct ScriptCommandBuilder {
+    lines: RefCell<Vec<String>>,
+}
+
+impl ScriptCommandBuilder {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn lines(&self) -> Vec<String> {
+        self.lines.borrow().clone()
+    }
+
+    fn to_line(&self, command: &Command) -> String {
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| {
+                let arg = arg.to_string_lossy();
+
+                match arg.strip_prefix(PANE_ID_PREFIX) {
+                    Some(n) => format!("\"$pane_{}\"", n),
+                    None => shell_quote(&arg),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Stands for the id of a pane created by the script, which tmux would print as `%<n>`.
+const PANE_ID_PREFIX: &str = "%stmux-pane-";
+
+impl CommandBuilder for ScriptCommandBuilder {
+    fn new_command(&self) -> Command {
+        Command::new("tmux")
+    }
+
+    fn status(&self, command: &mut Command) -> io::Result<ExitStatus> {
+        let line = self.to_line(command);
+        self.lines.borrow_mut().push(line);
+
+        Ok(ExitStatus::from_raw(0))
+    }
+
+    fn output(&self, command: &mut Command) -> io::Result<Output> {
+        let line = self.to_line(command);
+        let mut lines = self.lines.borrow_mut();
+        let n = lines.iter().filter(|l| l.starts_with("pane_")).count() + 1;
+        lines.push(format!("pane_{}=$({})", n, line));
+
+        Ok(Output {
+            status: ExitStatus::from_raw(0),
+            stdout: format!("{}{}", PANE_ID_PREFIX, n).into_bytes(),
+            stderr: vec![],
+        })
+    }
+}
+
+/// Prints the commands changing anything instead of running them, for `--dry-run`. Queries are
+/// still run by `queries`, so that the decisions depending on them are the real ones.
+pub(crate) struct DryRunCommandBuilder<'q, Q: CommandBuilder> {
+    queries: &'q Q,
+    script: ScriptCommandBuilder,
+    /// Panes the printed commands added to the current window, which the server doesn't count.
+    split_panes: Cell<usize>,
+}
+
+impl<'q, Q: CommandBuilder> DryRunCommandBuilder<'q, Q> {
+    pub(crate) fn new(queries: &'q Q) -> Self {
+        Self {
+            queries,
+            script: ScriptCommandBuilder::new(),
+            split_panes: Cell::new(0),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn lines(&self) -> Vec<String> {
+        self.script.lines()
+    }
+
+    fn record(&self, command: &Command) {
+        let mut args = command.get_args().map(|arg| arg.to_string_lossy());
+
+        // Splits of other panes always target a pane the script created, e.g. on restore.
+        if args
+            .next()
+            .is_some_and(|subcommand| subcommand == "split-window")
+            && !args.any(|arg| arg.starts_with('%'))
+        {
+            self.split_panes.set(self.split_panes.get() + 1);
+        }
+
+        if let Some(line) = self.script.lines.borrow().last() {
+            println!("{}", line);
+        }
+    }
+
+    /// Runs a query, counting the panes the printed commands would have created.
+    fn query(&self, command: &mut Command) -> io::Result<Output> {
+        let mut output = self.queries.output(command)?;
+
+        if command.get_args().any(|arg| arg == WINDOW_PANES)
+            && let Ok(count) = String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .parse::<usize>()
+        {
+            output.stdout = format!("{}\n", count + self.split_panes.get()).into_bytes();
+        }
+
+        Ok(output)
+    }
+}
+
+const WINDOW_PANES: &str = "#{window_panes}";
+
+/// Subcommands which only read from tmux.
+const QUERIES: [&str; 8] = [
+    "list-sessions",
+    "list-windows",
+    "list-panes",
+    "has-session",
+    "show-option",
+    "show-options",
+    "show-window-options",
+    "capture-pane",
+];
+
+fn is_query(command: &Command) -> bool {
+    let mut args = command.get_args().map(|arg| arg.to_string_lossy());
+
+    match args.next() {
+        Some(subcommand) if subcommand == "display-message" => args.any(|arg| arg == "-p"),
+        Some(subcommand) => QUERIES.contains(&subcommand.as_ref()),
+        None => false,
+    }
+}
+
+impl<Q: CommandBuilder> CommandBuilder for DryRunCommandBuilder<'_, Q> {
+    fn new_command(&self) -> Command {
+        self.queries.new_command()
+    }
+
+    fn status(&self, command: &mut Command) -> io::Result<ExitStatus> {
+        if is_query(command) {
+            return self.queries.status(command);
+        }
+
+        let status = self.script.status(command);
+        self.record(command);
+        status
+    }
+
+    fn output(&self, command: &mut Command) -> io::Result<Output> {
+        if is_query(command) {
+            return self.query(command);
+        }
+
+        let output = self.script.output(command);
+        self.record(command);
+        output
+    }
+}
+
 #[cfg(test)]
 pub(crate) struct TestCommandBuilderImpl {
     socket: String,
@@ -45,3 +229,25 @@ impl CommandBuilder for TestCommandBuilderImpl {
         cmd
     }
 }
+
+#[cfg(test)]
+mod is_query_tests {
+    use super::*;
+
+    #[test]
+    fn should_run_only_commands_reading_from_tmux() {
+        // Given
+        let mut display = Command::new("tmux");
+        display.args(["display-message", "-p", "#S"]);
+        let mut message = Command::new("tmux");
+        message.args(["display-message", "Saved."]);
+        let mut split = Command::new("tmux");
+        split.args(["split-window", "-h"]);
+
+        // When
+        let result = [&display, &message, &split].map(is_query);
+
+        // Then
+        assert_eq!(result, [true, false, false]);
+    }
+}
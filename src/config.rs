@@ -2,21 +2,32 @@ use dirs_next::home_dir;
 use mockall::automock;
 use std::{fs, path::PathBuf};
 
+use crate::error::{Error, Result};
+use crate::settings::{Settings, SettingsFile, SettingsFileImpl};
+
 const CONFIG_LOCATION: &str = ".config/stmux";
 const STATUS_CONFIG_FILENAME: &str = "status.toml";
 const SESSIONS_FILENAME: &str = "sessions.toml";
 const RECENT_SESSIONS_FILENAME: &str = "recent_sessions";
-const BOOKMARKS_FILENAME: &str = "bookmarks";
+const BOOKMARKS_FILENAME: &str = "bookmarks.toml";
 const NEOVIM_CONFIG_FILENAME: &str = "nvim-config.lua";
+const SETTINGS_FILENAME: &str = "config.toml";
+const SNAPSHOTS_DIRNAME: &str = "snapshots";
+const TEMPLATES_FILENAME: &str = "templates.toml";
 
 #[automock]
 pub(crate) trait Config {
-    fn create_dir(&self);
+    fn create_dir(&self) -> Result<()>;
     fn status_config_filename(&self) -> String;
     fn sessions_filename(&self) -> String;
     fn recent_sessions_filename(&self) -> String;
     fn bookmarks_filename(&self) -> String;
     fn neovim_config_filename(&self) -> String;
+    fn settings_filename(&self) -> String;
+    fn snapshots_dirname(&self) -> String;
+    fn templates_filename(&self) -> String;
+    /// Contents of `config.toml`, defaults if it doesn't exist.
+    fn settings(&self) -> Result<Settings>;
 }
 
 pub(crate) struct ConfigImpl;
@@ -39,12 +50,14 @@ impl ConfigImpl {
 }
 
 impl Config for ConfigImpl {
-    fn create_dir(&self) {
+    fn create_dir(&self) -> Result<()> {
         let config_dir = ConfigImpl::config_location();
 
         if !config_dir.is_dir() {
-            fs::create_dir_all(&config_dir).expect("Failed to create config directory.");
+            fs::create_dir_all(&config_dir).map_err(Error::io(config_dir.display()))?;
         }
+
+        Ok(())
     }
 
     fn status_config_filename(&self) -> String {
@@ -66,4 +79,20 @@ impl Config for ConfigImpl {
     fn neovim_config_filename(&self) -> String {
         ConfigImpl::filename_at_config(NEOVIM_CONFIG_FILENAME)
     }
+
+    fn settings_filename(&self) -> String {
+        ConfigImpl::filename_at_config(SETTINGS_FILENAME)
+    }
+
+    fn snapshots_dirname(&self) -> String {
+        ConfigImpl::filename_at_config(SNAPSHOTS_DIRNAME)
+    }
+
+    fn templates_filename(&self) -> String {
+        ConfigImpl::filename_at_config(TEMPLATES_FILENAME)
+    }
+
+    fn settings(&self) -> Result<Settings> {
+        SettingsFileImpl::new(&self.settings_filename()).load()
+    }
 }
@@ -0,0 +1,344 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{
+    layout,
+    model::{SessionName, TmuxSession, TmuxWindow},
+};
+
+/// A difference between a stored session and the live one. Windows and panes are matched by
+/// index.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub(crate) enum Change {
+    NotRunning,
+    NotStored,
+    WindowAdded {
+        window: usize,
+        name: String,
+    },
+    WindowRemoved {
+        window: usize,
+        name: String,
+    },
+    WindowRenamed {
+        window: usize,
+        from: String,
+        to: String,
+    },
+    LayoutChanged {
+        window: usize,
+        from: String,
+        to: String,
+    },
+    PaneAdded {
+        window: usize,
+        pane: usize,
+        path: String,
+    },
+    PaneRemoved {
+        window: usize,
+        pane: usize,
+        path: String,
+    },
+    PathChanged {
+        window: usize,
+        pane: usize,
+        from: String,
+        to: String,
+    },
+    /// The pane name (`@window-name`) was set, changed or unset.
+    PaneRenamed {
+        window: usize,
+        pane: usize,
+        from: Option<String>,
+        to: Option<String>,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::NotRunning => write!(f, "not running"),
+            Change::NotStored => write!(f, "not stored"),
+            Change::WindowAdded { window, name } => {
+                write!(f, "{}: window '{}' added", window, name)
+            }
+            Change::WindowRemoved { window, name } => {
+                write!(f, "{}: window '{}' removed", window, name)
+            }
+            Change::WindowRenamed { window, from, to } => {
+                write!(f, "{}: window renamed '{}' -> '{}'", window, from, to)
+            }
+            Change::LayoutChanged { window, from, to } => {
+                write!(f, "{}: layout {} -> {}", window, from, to)
+            }
+            Change::PaneAdded { window, pane, path } => {
+                write!(f, "{}.{}: pane added in {}", window, pane, path)
+            }
+            Change::PaneRemoved { window, pane, path } => {
+                write!(f, "{}.{}: pane removed from {}", window, pane, path)
+            }
+            Change::PathChanged {
+                window,
+                pane,
+                from,
+                to,
+            } => write!(f, "{}.{}: path {} -> {}", window, pane, from, to),
+            Change::PaneRenamed {
+                window,
+                pane,
+                from,
+                to,
+            } => write!(
+                f,
+                "{}.{}: pane renamed '{}' -> '{}'",
+                window,
+                pane,
+                from.as_deref().unwrap_or_default(),
+                to.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct SessionDiff {
+    pub(crate) session_name: SessionName,
+    pub(crate) changes: Vec<Change>,
+}
+
+/// Changes that turn the stored session into the live one.
+pub(crate) fn diff(stored: Option<&TmuxSession>, live: Option<&TmuxSession>) -> Vec<Change> {
+    let (stored, live) = match (stored, live) {
+        (Some(stored), Some(live)) => (stored, live),
+        (Some(_), None) => return vec![Change::NotRunning],
+        (None, Some(_)) => return vec![Change::NotStored],
+        (None, None) => return vec![],
+    };
+    let mut changes = vec![];
+
+    for window in &stored.windows {
+        if !live.windows.iter().any(|w| w.index == window.index) {
+            changes.push(Change::WindowRemoved {
+                window: window.index,
+                name: window.name.clone(),
+            });
+        }
+    }
+
+    for live_window in &live.windows {
+        match stored.windows.iter().find(|w| w.index == live_window.index) {
+            Some(stored_window) => window_changes(stored_window, live_window, &mut changes),
+            None => changes.push(Change::WindowAdded {
+                window: live_window.index,
+                name: live_window.name.clone(),
+            }),
+        }
+    }
+
+    changes
+}
+
+fn window_changes(stored: &TmuxWindow, live: &TmuxWindow, changes: &mut Vec<Change>) {
+    let window = live.index;
+
+    if stored.name != live.name {
+        changes.push(Change::WindowRenamed {
+            window,
+            from: stored.name.clone(),
+            to: live.name.clone(),
+        });
+    }
+
+    if !layout::same_geometry(&stored.layout, &live.layout) {
+        changes.push(Change::LayoutChanged {
+            window,
+            from: stored.layout.clone(),
+            to: live.layout.clone(),
+        });
+    }
+
+    for pane in &stored.panes {
+        if !live.panes.iter().any(|p| p.index == pane.index) {
+            changes.push(Change::PaneRemoved {
+                window,
+                pane: pane.index,
+                path: pane.path.clone(),
+            });
+        }
+    }
+
+    for live_pane in &live.panes {
+        match stored.panes.iter().find(|p| p.index == live_pane.index) {
+            Some(stored_pane) => {
+                if stored_pane.path != live_pane.path {
+                    changes.push(Change::PathChanged {
+                        window,
+                        pane: live_pane.index,
+                        from: stored_pane.path.clone(),
+                        to: live_pane.path.clone(),
+                    });
+                }
+
+                if stored_pane.name != live_pane.name {
+                    changes.push(Change::PaneRenamed {
+                        window,
+                        pane: live_pane.index,
+                        from: stored_pane.name.clone(),
+                        to: live_pane.name.clone(),
+                    });
+                }
+            }
+            None => changes.push(Change::PaneAdded {
+                window,
+                pane: live_pane.index,
+                path: live_pane.path.clone(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod diff_tests {
+    use super::*;
+    use crate::model::TmuxPane;
+
+    fn window(index: usize, name: &str, layout: &str, paths: &[&str]) -> TmuxWindow {
+        TmuxWindow {
+            index,
+            name: name.to_string(),
+            layout: layout.to_string(),
+            panes: paths
+                .iter()
+                .enumerate()
+                .map(|(i, path)| TmuxPane {
+                    index: i + 1,
+                    path: path.to_string(),
+                    active: i == 0,
+                    startup_command: None,
+                    shell_command: None,
+                    environment: vec![],
+                    name: None,
+                })
+                .collect(),
+            options: vec![],
+            active: Some(index == 1),
+            environment: vec![],
+        }
+    }
+
+    fn session(windows: Vec<TmuxWindow>) -> TmuxSession {
+        TmuxSession {
+            background: None,
+            no_recent_tracking: None,
+            windows,
+            options: vec![],
+            environment: vec![],
+            template: None,
+        }
+    }
+
+    #[test]
+    fn should_report_window_and_pane_changes() {
+        // Given
+        let stored = session(vec![
+            window(
+                1,
+                "code",
+                "b25d,80x24,0,0{40x24,0,0,1,39x24,41,0,2}",
+                &["/src", "/tmp"],
+            ),
+            window(2, "logs", "a19d,50x20,0,0,3", &["/var/log"]),
+        ]);
+        let live = session(vec![
+            window(
+                1,
+                "editor",
+                "b25d,80x24,0,0{40x24,0,0,7,39x24,41,0,8}",
+                &["/src", "/etc"],
+            ),
+            window(3, "shell", "a19d,50x20,0,0,9", &["/home"]),
+        ]);
+
+        // When
+        let result = diff(Some(&stored), Some(&live));
+
+        // Then
+        assert_eq!(
+            result,
+            vec![
+                Change::WindowRemoved {
+                    window: 2,
+                    name: "logs".to_string(),
+                },
+                Change::WindowRenamed {
+                    window: 1,
+                    from: "code".to_string(),
+                    to: "editor".to_string(),
+                },
+                Change::PathChanged {
+                    window: 1,
+                    pane: 2,
+                    from: "/tmp".to_string(),
+                    to: "/etc".to_string(),
+                },
+                Change::WindowAdded {
+                    window: 3,
+                    name: "shell".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_a_changed_layout() {
+        // Given
+        let stored = session(vec![window(1, "code", "a19d,50x20,0,0,0", &["/src"])]);
+        let live = session(vec![window(
+            1,
+            "code",
+            "b25d,80x24,0,0{40x24,0,0,1,39x24,41,0,2}",
+            &["/src", "/src"],
+        )]);
+
+        // When
+        let result = diff(Some(&stored), Some(&live));
+
+        // Then
+        assert!(matches!(result[0], Change::LayoutChanged { window: 1, .. }));
+        assert!(matches!(
+            result[1],
+            Change::PaneAdded {
+                window: 1,
+                pane: 2,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn should_report_a_renamed_pane() {
+        // Given
+        let layout = "b25d,80x24,0,0{40x24,0,0,1,39x24,41,0,2}";
+        let stored = session(vec![window(1, "code", layout, &["/src", "/src"])]);
+        let mut live = session(vec![window(1, "code", layout, &["/src", "/src"])]);
+        live.windows[0].panes[1].name = Some("api".to_string());
+
+        // When
+        let result = diff(Some(&stored), Some(&live));
+
+        // Then
+        assert_eq!(
+            result,
+            vec![Change::PaneRenamed {
+                window: 1,
+                pane: 2,
+                from: None,
+                to: Some("api".to_string()),
+            }]
+        );
+        assert_eq!(result[0].to_string(), "1.2: pane renamed '' -> 'api'");
+    }
+}
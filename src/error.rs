@@ -0,0 +1,56 @@
+use std::{fmt, io};
+
+#[derive(Debug)]
+pub(crate) enum Error {
+    /// Reading or writing a file failed.
+    Io { path: String, source: io::Error },
+    /// A file was read, but its content is not valid.
+    Parse { path: String, message: String },
+    /// A tmux command couldn't be run or returned something unexpected.
+    Tmux(String),
+    /// A named session, version, etc. doesn't exist.
+    NotFound(String),
+    /// A session, etc. would be overwritten.
+    AlreadyExists(String),
+}
+
+pub(crate) type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub(crate) fn io(path: impl fmt::Display) -> impl FnOnce(io::Error) -> Error {
+        let path = path.to_string();
+        move |source| Error::Io { path, source }
+    }
+
+    pub(crate) fn parse(path: impl fmt::Display, message: impl fmt::Display) -> Error {
+        Error::Parse {
+            path: path.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Distinct exit codes, so scripts and keybindings can tell failures apart.
+    pub(crate) fn exit_code(&self) -> i32 {
+        match self {
+            Error::Io { .. } => 2,
+            Error::Parse { .. } => 3,
+            Error::Tmux(_) => 4,
+            Error::NotFound(_) => 5,
+            Error::AlreadyExists(_) => 6,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path, source),
+            Error::Parse { path, message } => write!(f, "Failed to parse {}: {}", path, message),
+            Error::Tmux(message) => write!(f, "{}", message),
+            Error::NotFound(message) => write!(f, "{}", message),
+            Error::AlreadyExists(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
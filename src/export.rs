@@ -0,0 +1,189 @@
+use crate::{
+    command_builder::ScriptCommandBuilder,
+    error::{Error, Result},
+    model::EnvironmentVariable,
+    sessions::{SessionStorage, SessionStorageImpl},
+    settings::RestoreSettings,
+    tmux::TmuxImpl,
+    utils::shell_quote,
+};
+
+/// Writes a bash script recreating a stored session with plain tmux commands, the same ones
+/// `SessionStorage::restore` runs, and attaching to it. Stored pane contents are left out, the
+/// script is meant for machines without them.
+pub(crate) fn shell_script(
+    sessions_filename: &str,
+    session_name: &str,
+    restore_settings: &RestoreSettings,
+    environment: &[EnvironmentVariable],
+) -> Result<String> {
+    let command_builder = ScriptCommandBuilder::new();
+    let tmux = TmuxImpl::new(&command_builder, environment);
+    // The delay becomes a `sleep` in the script.
+    let no_delay = RestoreSettings {
+        layout_delay_millis: 0,
+    };
+    let sessions =
+        SessionStorageImpl::new(sessions_filename, &tmux, &no_delay).without_scrollback();
+
+    let Some(background) = sessions.restore(session_name)? else {
+        return Err(Error::NotFound(format!(
+            "Session '{}' not found.",
+            session_name
+        )));
+    };
+
+    let target = shell_quote(&format!("={}", session_name));
+    let mut script = vec![
+        "#!/usr/bin/env bash".to_string(),
+        format!("# Recreates the tmux session '{}'.", session_name),
+        "set -e".to_string(),
+        String::new(),
+        format!("if tmux has-session -t {} 2>/dev/null; then", target),
+        format!(
+            "    echo {} >&2",
+            shell_quote(&format!("Session '{}' already exists.", session_name))
+        ),
+        "    exit 1".to_string(),
+        "fi".to_string(),
+        String::new(),
+    ];
+    let mut delayed = false;
+
+    for line in command_builder.lines() {
+        if !delayed && line.starts_with("tmux select-layout") {
+            if restore_settings.layout_delay_millis > 0 {
+                script.push(format!(
+                    "sleep {}",
+                    restore_settings.layout_delay_millis as f64 / 1000.0
+                ));
+            }
+
+            delayed = true;
+        }
+
+        script.push(line);
+    }
+
+    if !background {
+        script.extend([
+            String::new(),
+            "if [ -n \"$TMUX\" ]; then".to_string(),
+            format!("    tmux switch-client -t {}", target),
+            "else".to_string(),
+            format!("    tmux attach-session -t {}", target),
+            "fi".to_string(),
+        ]);
+    }
+
+    Ok(script.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod shell_script_tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        scrollback::{PaneContent, Scrollback, ScrollbackImpl},
+        test_utils::TempDir,
+    };
+
+    #[test]
+    fn should_write_the_commands_restore_runs() {
+        // Given
+        let directory = TempDir::new();
+        let filename = directory.filename("sessions.toml");
+        fs::write(
+            &filename,
+            r#"
+[work]
+options = [{ name = "status", value = "off" }]
+environment = [{ name = "FOO", value = "a b" }]
+
+[[work.windows]]
+index = 1
+name = "code"
+layout = "020a,80x24,0,0{40x24,0,0,1,39x24,41,0,2}"
+options = []
+active = true
+
+[[work.windows.panes]]
+index = 1
+path = "/src"
+active = false
+shell_command = "nvim"
+
+[[work.windows.panes]]
+index = 2
+path = "/tmp"
+active = true
+"#,
+        )
+        .unwrap();
+
+        // When
+        let result = shell_script(&filename, "work", &RestoreSettings::default(), &[]);
+
+        // Then
+        let script = result.unwrap();
+        let commands = script
+            .lines()
+            .filter(|line| {
+                line.starts_with("pane_") || line.starts_with("tmux") || line.starts_with("sleep")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            commands,
+            vec![
+                "pane_1=$(tmux new-session -d -P -F '#{pane_id}' -s work -n code -x 80 -y 24 -e 'FOO=a b' -c /src)",
+                "pane_2=$(tmux split-window -P -F '#{pane_id}' -h -t \"$pane_1\" -l 39 -c /tmp -e 'FOO=a b')",
+                "tmux send-keys -t \"$pane_1\" nvim C-m",
+                "tmux select-pane -t \"$pane_2\"",
+                "tmux set -t work status off",
+                "tmux select-window -t \"$pane_1\"",
+                "sleep 0.3",
+                "tmux select-layout -t work:code '020a,80x24,0,0{40x24,0,0,1,39x24,41,0,2}'",
+            ]
+        );
+    }
+
+    #[test]
+    fn should_leave_out_stored_pane_contents() {
+        // Given
+        let directory = TempDir::new();
+        let filename = directory.filename("sessions.toml");
+        fs::write(
+            &filename,
+            r#"
+[work]
+windows = [
+    { index = 1, name = "code", layout = "", options = [], panes = [
+        { index = 1, path = "/src", active = true },
+    ] },
+]
+"#,
+        )
+        .unwrap();
+        let pane = PaneContent {
+            window_index: 1,
+            pane_index: 1,
+            content: "output".to_string(),
+        };
+        ScrollbackImpl::new(&filename)
+            .save("work", vec![pane], 1024)
+            .unwrap();
+
+        // When
+        let result = shell_script(&filename, "work", &RestoreSettings::default(), &[]);
+
+        // Then
+        let script = result.unwrap();
+        assert!(
+            script.contains("new-session -d -P -F '#{pane_id}' -s work -n code -c /src)\n"),
+            "Was: {}",
+            script
+        );
+        assert!(!script.contains("scrollback"), "Was: {}", script);
+    }
+}
@@ -0,0 +1,691 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde_yaml::{Mapping, Value};
+
+use crate::{
+    args::ImportFormat,
+    error::{Error, Result},
+    layout,
+    model::{
+        EnvironmentVariable, NameValue, TmuxOption, TmuxPane, TmuxSession, TmuxSessions, TmuxWindow,
+    },
+    process,
+    projects::expand_home,
+    settings::SaveSettings,
+    utils,
+};
+
+/// Sessions converted from a tmuxinator or tmuxp project file, or a tmux-resurrect save file.
+#[derive(Debug)]
+pub(crate) struct Imported {
+    pub(crate) sessions: TmuxSessions,
+    /// Features of the file stmux has no counterpart for, e.g. `on_project_start`.
+    pub(crate) unmapped: Vec<String>,
+}
+
+pub(crate) trait ProjectFile {
+    fn import(&self, format: &ImportFormat) -> Result<Imported>;
+}
+
+pub(crate) struct ProjectFileImpl {
+    filename: String,
+    /// Decides which of the commands recorded by tmux-resurrect are restored.
+    save_settings: SaveSettings,
+}
+
+impl ProjectFileImpl {
+    pub(crate) fn new(filename: &str, save_settings: &SaveSettings) -> Self {
+        Self {
+            filename: filename.to_string(),
+            save_settings: save_settings.clone(),
+        }
+    }
+}
+
+impl ProjectFile for ProjectFileImpl {
+    fn import(&self, format: &ImportFormat) -> Result<Imported> {
+        let content = fs::read_to_string(&self.filename).map_err(Error::io(&self.filename))?;
+        let mut converter = Converter::default();
+
+        let sessions = match format {
+            ImportFormat::Tmuxinator => yaml(&content)
+                .and_then(|project| converter.tmuxinator(&project))
+                .map(|session| HashMap::from([session])),
+            ImportFormat::Tmuxp => yaml(&content)
+                .and_then(|project| converter.tmuxp(&project))
+                .map(|session| HashMap::from([session])),
+            ImportFormat::Resurrect => converter.resurrect(&content, &self.save_settings),
+        };
+
+        sessions
+            .map(|sessions| Imported {
+                sessions,
+                unmapped: converter.unmapped,
+            })
+            .map_err(|message| Error::parse(&self.filename, message))
+    }
+}
+
+/// Pane settings inherited from the session and window.
+#[derive(Clone, Default)]
+struct Inherited {
+    path: Option<String>,
+    commands: Vec<String>,
+}
+
+#[derive(Default)]
+struct Converter {
+    unmapped: Vec<String>,
+}
+
+impl Converter {
+    fn tmuxinator(
+        &mut self,
+        project: &Mapping,
+    ) -> std::result::Result<(String, TmuxSession), String> {
+        let mut name = None;
+        let mut inherited = Inherited::default();
+        let mut windows = vec![];
+        let mut startup_window = None;
+        let mut startup_pane = None;
+
+        for (key, value) in project {
+            match key_name(key).as_str() {
+                "name" | "project_name" => name = scalar(value),
+                "root" | "project_root" => inherited.path = directory(None, value),
+                "pre_window" | "pre_tab" => inherited.commands = commands(value),
+                "windows" | "tabs" => windows = sequence(value),
+                "startup_window" => startup_window = scalar(value),
+                "startup_pane" => startup_pane = scalar(value),
+                key => self.unmapped.push(key.to_string()),
+            }
+        }
+
+        let windows = windows
+            .iter()
+            .enumerate()
+            .map(|(i, window)| self.tmuxinator_window(i + 1, window, &inherited))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok((
+            session_name(name, "name")?,
+            session(windows, startup_window, startup_pane, vec![], vec![]),
+        ))
+    }
+
+    /// A `name: command(s)` or `name: { root, layout, panes, ... }` entry.
+    fn tmuxinator_window(
+        &mut self,
+        index: usize,
+        window: &Value,
+        inherited: &Inherited,
+    ) -> std::result::Result<TmuxWindow, String> {
+        let Some((name, value)) = window.as_mapping().and_then(|m| m.iter().next()) else {
+            return Err(format!("Window {} is not a 'name: ...' entry.", index));
+        };
+        let name = key_name(name);
+        let mut inherited = inherited.clone();
+        let mut layout = String::new();
+        let mut options = vec![];
+        let mut panes = vec![];
+
+        match value {
+            Value::Mapping(window) => {
+                for (key, value) in window {
+                    match key_name(key).as_str() {
+                        "root" => inherited.path = directory(inherited.path.as_deref(), value),
+                        "pre" => inherited.commands.extend(commands(value)),
+                        "layout" => layout = scalar(value).unwrap_or_default(),
+                        "panes" => panes = sequence(value),
+                        "synchronize" => options.push(TmuxOption {
+                            name: "synchronize-panes".to_string(),
+                            value: "on".to_string(),
+                        }),
+                        key => self.unmapped.push(format!("{}.{}", name, key)),
+                    }
+                }
+            }
+            commands => panes = vec![commands.clone()],
+        }
+
+        if panes.is_empty() {
+            panes.push(Value::Null);
+        }
+
+        let panes = panes
+            .iter()
+            .enumerate()
+            .map(|(i, pane)| {
+                let pane_commands = match pane.as_mapping().and_then(|m| m.iter().next()) {
+                    Some((title, pane_commands)) => {
+                        self.unmapped.push(format!(
+                            "{}.panes.{} title '{}'",
+                            name,
+                            i + 1,
+                            key_name(title)
+                        ));
+                        pane_commands
+                    }
+                    None => pane,
+                };
+
+                pane_with(i + 1, &inherited, &commands(pane_commands), vec![])
+            })
+            .collect();
+
+        Ok(self.window(index, name, layout, panes, options, vec![]))
+    }
+
+    fn tmuxp(&mut self, project: &Mapping) -> std::result::Result<(String, TmuxSession), String> {
+        let mut name = None;
+        let mut inherited = Inherited::default();
+        let mut environment = vec![];
+        let mut options = vec![];
+        let mut windows = vec![];
+
+        for (key, value) in project {
+            match key_name(key).as_str() {
+                "session_name" => name = scalar(value),
+                "start_directory" => inherited.path = directory(None, value),
+                "shell_command_before" => inherited.commands = commands(value),
+                "environment" => environment = name_values(value),
+                "options" => options = name_values(value),
+                "windows" => windows = sequence(value),
+                key => self.unmapped.push(key.to_string()),
+            }
+        }
+
+        let mut focused_window = None;
+        let mut focused_pane = None;
+        let mut converted = vec![];
+
+        for (i, window) in windows.iter().enumerate() {
+            let Some(window) = window.as_mapping() else {
+                return Err(format!("Window {} is not a mapping.", i + 1));
+            };
+            let (window, focused) = self.tmuxp_window(i + 1, window, &inherited);
+
+            if let Some(focused) = focused {
+                focused_window = Some(window.name.clone());
+                focused_pane = focused.map(|index| index.to_string());
+            }
+
+            converted.push(window);
+        }
+
+        Ok((
+            session_name(name, "session_name")?,
+            session(
+                converted,
+                focused_window,
+                focused_pane,
+                options,
+                environment,
+            ),
+        ))
+    }
+
+    /// Returns the window and, if it has the focus, the index of its focused pane.
+    fn tmuxp_window(
+        &mut self,
+        index: usize,
+        window: &Mapping,
+        inherited: &Inherited,
+    ) -> (TmuxWindow, Option<Option<usize>>) {
+        let mut inherited = inherited.clone();
+        let mut name = index.to_string();
+        let mut layout = String::new();
+        let mut options = vec![];
+        let mut environment = vec![];
+        let mut panes = vec![];
+        let mut focus = false;
+
+        for (key, value) in window {
+            match key_name(key).as_str() {
+                "window_name" => name = scalar(value).unwrap_or(name),
+                "start_directory" => inherited.path = directory(inherited.path.as_deref(), value),
+                "shell_command_before" => inherited.commands.extend(commands(value)),
+                "layout" => layout = scalar(value).unwrap_or_default(),
+                "options" => options = name_values(value),
+                "environment" => environment = name_values(value),
+                "panes" => panes = sequence(value),
+                "focus" => focus = value.as_bool().unwrap_or(false),
+                key => self.unmapped.push(format!("{}.{}", name, key)),
+            }
+        }
+
+        if panes.is_empty() {
+            panes.push(Value::Null);
+        }
+
+        let mut focused_pane = None;
+        let panes = panes
+            .iter()
+            .enumerate()
+            .map(|(i, pane)| {
+                let Some(pane) = pane.as_mapping() else {
+                    return pane_with(i + 1, &inherited, &commands(pane), vec![]);
+                };
+                let mut inherited = inherited.clone();
+                let mut pane_commands = vec![];
+                let mut environment = vec![];
+
+                for (key, value) in pane {
+                    match key_name(key).as_str() {
+                        "shell_command" => pane_commands = commands(value),
+                        "start_directory" => {
+                            inherited.path = directory(inherited.path.as_deref(), value)
+                        }
+                        "environment" => environment = name_values(value),
+                        "focus" if value.as_bool() == Some(true) => focused_pane = Some(i + 1),
+                        "focus" => {}
+                        key => self
+                            .unmapped
+                            .push(format!("{}.panes.{}.{}", name, i + 1, key)),
+                    }
+                }
+
+                pane_with(i + 1, &inherited, &pane_commands, environment)
+            })
+            .collect();
+
+        let window = self.window(index, name, layout, panes, options, environment);
+        (window, focus.then_some(focused_pane))
+    }
+
+    /// Reads the tab separated `pane`, `window` and `state` lines of a tmux-resurrect save file.
+    fn resurrect(
+        &mut self,
+        content: &str,
+        settings: &SaveSettings,
+    ) -> std::result::Result<TmuxSessions, String> {
+        let mut sessions = TmuxSessions::new();
+        let mut panes = vec![];
+
+        for (line_number, line) in content.lines().enumerate() {
+            let fields = line.split('\t').collect::<Vec<&str>>();
+            let number = |i: usize| {
+                fields[i].parse::<usize>().map_err(|_| {
+                    format!("Line {}: '{}' is not a number.", line_number + 1, fields[i])
+                })
+            };
+
+            match fields[0] {
+                "pane" if fields.len() >= 10 => {
+                    // Files written before pane titles were recorded have one field less.
+                    let offset = if fields.len() >= 11 { 1 } else { 0 };
+                    let full_command = fields[9 + offset].trim_start_matches(':');
+                    let command = match full_command.is_empty() {
+                        true => fields[8 + offset],
+                        false => full_command,
+                    };
+
+                    panes.push((
+                        fields[1].to_string(),
+                        number(2)?,
+                        TmuxPane {
+                            index: number(5)?,
+                            path: fields[6 + offset]
+                                .trim_start_matches(':')
+                                .replace("\\ ", " "),
+                            active: fields[7 + offset] == "1",
+                            startup_command: process::restorable_command_line(command, settings),
+                            shell_command: None,
+                            environment: vec![],
+                            name: None,
+                        },
+                    ));
+                }
+                "window" if fields.len() >= 7 => {
+                    let session = sessions
+                        .entry(fields[1].to_string())
+                        .or_insert_with(|| session(vec![], None, None, vec![], vec![]));
+                    let name = fields[3].trim_start_matches(':').to_string();
+                    let mut window = self.window(
+                        number(2)?,
+                        name,
+                        fields[6].to_string(),
+                        vec![],
+                        vec![],
+                        vec![],
+                    );
+                    window.active = Some(fields[4] == "1");
+                    session.windows.push(window);
+                }
+                "state" => {
+                    if let Some(attached) = fields.get(1).filter(|name| !name.is_empty()) {
+                        self.unmapped
+                            .push(format!("state (attached session '{}')", attached));
+                    }
+                }
+                "" => {}
+                kind => self
+                    .unmapped
+                    .push(format!("line {} ({})", line_number + 1, kind)),
+            }
+        }
+
+        for (session_name, window_index, pane) in panes {
+            let Some(window) = sessions
+                .get_mut(&session_name)
+                .and_then(|s| s.windows.iter_mut().find(|w| w.index == window_index))
+            else {
+                return Err(format!(
+                    "Pane {} of window {}:{} has no window line.",
+                    pane.index, session_name, window_index
+                ));
+            };
+
+            window.panes.push(pane);
+        }
+
+        for session in sessions.values_mut() {
+            session.windows.sort_by_key(|w| w.index);
+
+            for window in &mut session.windows {
+                window.panes.sort_by_key(|p| p.index);
+            }
+        }
+
+        Ok(sessions)
+    }
+
+    fn window(
+        &mut self,
+        index: usize,
+        name: String,
+        layout: String,
+        panes: Vec<TmuxPane>,
+        options: Vec<TmuxOption>,
+        environment: Vec<EnvironmentVariable>,
+    ) -> TmuxWindow {
+        if !layout.is_empty() && layout::check(&layout) == layout::Check::Invalid {
+            self.unmapped.push(format!("{}.layout '{}'", name, layout));
+        }
+
+        let layout = match layout::check(&layout) {
+            layout::Check::Valid => layout,
+            layout::Check::Repairable(layout) => layout,
+            layout::Check::Invalid => String::new(),
+        };
+
+        TmuxWindow {
+            index,
+            name,
+            layout,
+            panes,
+            options,
+            active: Some(false),
+            environment,
+        }
+    }
+}
+
+/// Marks the active window and pane, the first ones unless given by name or index.
+fn session(
+    mut windows: Vec<TmuxWindow>,
+    active_window: Option<String>,
+    active_pane: Option<String>,
+    options: Vec<TmuxOption>,
+    environment: Vec<EnvironmentVariable>,
+) -> TmuxSession {
+    let active_window = active_window
+        .and_then(|active| {
+            windows
+                .iter()
+                .position(|w| w.name == active || w.index.to_string() == active)
+        })
+        .unwrap_or(0);
+
+    if let Some(window) = windows.get_mut(active_window) {
+        window.active = Some(true);
+
+        let active_pane = active_pane
+            .and_then(|active| {
+                window
+                    .panes
+                    .iter()
+                    .position(|p| p.index.to_string() == active)
+            })
+            .unwrap_or(0);
+
+        if let Some(pane) = window.panes.get_mut(active_pane) {
+            pane.active = true;
+        }
+    }
+
+    TmuxSession {
+        background: None,
+        no_recent_tracking: None,
+        windows,
+        options,
+        environment,
+        template: None,
+    }
+}
+
+fn pane_with(
+    index: usize,
+    inherited: &Inherited,
+    commands: &[String],
+    environment: Vec<EnvironmentVariable>,
+) -> TmuxPane {
+    let commands = inherited
+        .commands
+        .iter()
+        .chain(commands)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    TmuxPane {
+        index,
+        path: inherited.path.clone().unwrap_or_else(|| expand_home("~")),
+        active: false,
+        startup_command: None,
+        shell_command: (!commands.is_empty()).then(|| commands.join("; ")),
+        environment,
+        name: None,
+    }
+}
+
+fn yaml(content: &str) -> std::result::Result<Mapping, String> {
+    serde_yaml::from_str(content).map_err(|error| error.to_string())
+}
+
+fn session_name(name: Option<String>, key: &str) -> std::result::Result<String, String> {
+    name.map(|name| utils::valid_session_name(&name))
+        .ok_or(format!("Missing '{}'.", key))
+}
+
+/// Expands `~` and resolves relative directories against the inherited one.
+fn directory(base: Option<&str>, value: &Value) -> Option<String> {
+    let directory = expand_home(&scalar(value)?);
+
+    match base {
+        Some(base) if Path::new(&directory).is_relative() => Some(
+            Path::new(base)
+                .join(directory)
+                .to_string_lossy()
+                .to_string(),
+        ),
+        _ => Some(directory),
+    }
+}
+
+/// A single command, a list of commands or tmuxp's `- cmd: ...` entries.
+fn commands(value: &Value) -> Vec<String> {
+    match value {
+        Value::Sequence(items) => items.iter().flat_map(commands).collect(),
+        Value::Mapping(command) => command.get("cmd").and_then(scalar).into_iter().collect(),
+        value => scalar(value).into_iter().collect(),
+    }
+}
+
+fn name_values(value: &Value) -> Vec<NameValue> {
+    value
+        .as_mapping()
+        .into_iter()
+        .flatten()
+        .map(|(name, value)| NameValue {
+            name: key_name(name),
+            value: match value {
+                Value::Bool(true) => "on".to_string(),
+                Value::Bool(false) => "off".to_string(),
+                value => scalar(value).unwrap_or_default(),
+            },
+        })
+        .collect()
+}
+
+fn sequence(value: &Value) -> Vec<Value> {
+    value.as_sequence().cloned().unwrap_or_default()
+}
+
+fn key_name(key: &Value) -> String {
+    scalar(key).unwrap_or_default()
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod import_tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+
+    fn import(format: ImportFormat, content: &str) -> Imported {
+        let directory = TempDir::new();
+        let filename = directory.filename("project.yml");
+        fs::write(&filename, content).unwrap();
+        ProjectFileImpl::new(&filename, &SaveSettings::default())
+            .import(&format)
+            .unwrap()
+    }
+
+    #[test]
+    fn should_import_a_tmuxinator_project() {
+        // Given
+        let content = r#"
+name: blog
+root: /src/blog
+pre_window: nvm use
+on_project_start: docker compose up -d
+startup_window: server
+windows:
+  - editor:
+      layout: main-vertical
+      panes:
+        - vim
+        - logs:
+          - cd log
+          - tail -f dev.log
+  - server: bundle exec rails s
+  - docs:
+      root: doc
+"#;
+
+        // When
+        let result = import(ImportFormat::Tmuxinator, content);
+
+        // Then
+        let windows = &result.sessions["blog"].windows;
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows[0].layout, "main-vertical");
+        assert_eq!(
+            windows[0].panes[0].shell_command,
+            Some("nvm use; vim".to_string())
+        );
+        assert_eq!(
+            windows[0].panes[1].shell_command,
+            Some("nvm use; cd log; tail -f dev.log".to_string())
+        );
+        assert_eq!(windows[1].active, Some(true));
+        assert!(windows[1].panes[0].active);
+        assert_eq!(windows[2].panes[0].path, "/src/blog/doc");
+        assert_eq!(
+            result.unmapped,
+            vec!["on_project_start", "editor.panes.2 title 'logs'"]
+        );
+    }
+
+    #[test]
+    fn should_import_a_tmuxp_project() {
+        // Given
+        let content = r#"
+session_name: api
+start_directory: /src/api
+environment:
+  RUST_LOG: debug
+before_script: ./bootstrap.sh
+windows:
+  - window_name: code
+    panes:
+      - nvim
+  - window_name: run
+    focus: true
+    layout: even-horizontal
+    environment:
+      PORT: 8080
+    panes:
+      - shell_command:
+          - cmd: cargo watch -x run
+      - start_directory: tests
+        focus: true
+"#;
+
+        // When
+        let result = import(ImportFormat::Tmuxp, content);
+
+        // Then
+        let session = &result.sessions["api"];
+        assert_eq!(session.environment[0].value, "debug");
+        assert_eq!(session.windows[0].active, Some(false));
+        let run = &session.windows[1];
+        assert_eq!(run.active, Some(true));
+        assert_eq!(run.environment[0].value, "8080");
+        assert_eq!(
+            run.panes[0].shell_command,
+            Some("cargo watch -x run".to_string())
+        );
+        assert_eq!(run.panes[1].path, "/src/api/tests");
+        assert!(run.panes[1].active);
+        assert_eq!(result.unmapped, vec!["before_script"]);
+    }
+
+    #[test]
+    fn should_import_a_resurrect_save_file() {
+        // Given
+        let content = "\
+pane\twork\t1\t1\t:*\t1\thost\t:/src/my\\ app\t1\tnvim\t:nvim README.md
+pane\twork\t1\t1\t:*\t2\thost\t:/src\t0\tzsh\t:-zsh
+pane\twork\t2\t0\t:-\t1\thost\t:/tmp\t1\tbash\t:
+window\twork\t1\t:editor\t1\t:*\t9a3e,80x24,0,0{40x24,0,0,1,39x24,41,0,2}\t:
+window\twork\t2\t:shell\t0\t:-\tb25f,80x24,0,0,3\t:
+state\twork\t
+";
+
+        // When
+        let result = import(ImportFormat::Resurrect, content);
+
+        // Then
+        let windows = &result.sessions["work"].windows;
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].name, "editor");
+        assert_eq!(windows[0].active, Some(true));
+        assert_eq!(windows[0].panes[0].path, "/src/my app");
+        assert_eq!(
+            windows[0].panes[0].startup_command,
+            Some("nvim README.md".to_string())
+        );
+        assert_eq!(windows[0].panes[1].startup_command, None);
+        assert_eq!(windows[1].active, Some(false));
+        assert!(windows[1].panes[0].active);
+        assert_eq!(result.unmapped, vec!["state (attached session 'work')"]);
+    }
+}
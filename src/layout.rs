@@ -0,0 +1,396 @@
+use std::fmt;
+
+/// Direction in which a cell is divided. `Horizontal` cells are laid out left to right (`{...}` in
+/// tmux layout strings), `Vertical` ones top to bottom (`[...]`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SplitDirection {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum LayoutContent {
+    /// A single pane. Layouts written by old tmux versions don't have pane ids.
+    Pane(Option<usize>),
+    Split(SplitDirection, Vec<LayoutCell>),
+}
+
+/// A node of the tree described by a tmux layout string, e.g.
+/// `b25d,80x24,0,0{40x24,0,0,1,39x24,41,0,2}`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LayoutCell {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) x: usize,
+    pub(crate) y: usize,
+    pub(crate) content: LayoutContent,
+}
+
+impl LayoutCell {
+    /// Number of panes in this cell. tmux numbers panes in this (depth first) order.
+    pub(crate) fn pane_count(&self) -> usize {
+        match &self.content {
+            LayoutContent::Pane(_) => 1,
+            LayoutContent::Split(_, children) => children.iter().map(|c| c.pane_count()).sum(),
+        }
+    }
+
+    /// Width or height, whichever is along the given direction.
+    pub(crate) fn size(&self, direction: SplitDirection) -> usize {
+        match direction {
+            SplitDirection::Horizontal => self.width,
+            SplitDirection::Vertical => self.height,
+        }
+    }
+
+    /// The same cell with the pane ids removed, which change whenever a window is recreated.
+    fn without_pane_ids(&self) -> LayoutCell {
+        let content = match &self.content {
+            LayoutContent::Pane(_) => LayoutContent::Pane(None),
+            LayoutContent::Split(direction, children) => LayoutContent::Split(
+                *direction,
+                children.iter().map(|c| c.without_pane_ids()).collect(),
+            ),
+        };
+
+        LayoutCell {
+            content,
+            ..self.clone()
+        }
+    }
+
+    fn position(&self, direction: SplitDirection) -> usize {
+        match direction {
+            SplitDirection::Horizontal => self.x,
+            SplitDirection::Vertical => self.y,
+        }
+    }
+
+    /// Whether children exactly fill their parent, one cell of separator apart, as tmux requires.
+    fn is_consistent(&self) -> bool {
+        if self.width == 0 || self.height == 0 {
+            return false;
+        }
+
+        let LayoutContent::Split(direction, children) = &self.content else {
+            return true;
+        };
+        let across = match direction {
+            SplitDirection::Horizontal => SplitDirection::Vertical,
+            SplitDirection::Vertical => SplitDirection::Horizontal,
+        };
+        let mut offset = self.position(*direction);
+
+        for child in children {
+            if child.position(*direction) != offset
+                || child.position(across) != self.position(across)
+                || child.size(across) != self.size(across)
+                || !child.is_consistent()
+            {
+                return false;
+            }
+
+            offset += child.size(*direction) + 1;
+        }
+
+        offset == self.position(*direction) + self.size(*direction) + 1
+    }
+}
+
+/// The layout without its checksum, e.g. `80x24,0,0,1`.
+impl fmt::Display for LayoutCell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{},{},{}", self.width, self.height, self.x, self.y)?;
+
+        match &self.content {
+            LayoutContent::Pane(Some(id)) => write!(f, ",{}", id),
+            LayoutContent::Pane(None) => Ok(()),
+            LayoutContent::Split(direction, children) => {
+                let (open, close) = match direction {
+                    SplitDirection::Horizontal => ('{', '}'),
+                    SplitDirection::Vertical => ('[', ']'),
+                };
+
+                write!(f, "{}", open)?;
+
+                for (i, child) in children.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+
+                    write!(f, "{}", child)?;
+                }
+
+                write!(f, "{}", close)
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Check {
+    Valid,
+    /// Well formed, but the checksum is wrong or missing. Holds the corrected layout.
+    Repairable(String),
+    Invalid,
+}
+
+/// Layouts tmux arranges by name, e.g. `main-vertical`, instead of by cell geometry.
+const PRESETS: [&str; 7] = [
+    "even-horizontal",
+    "even-vertical",
+    "main-horizontal",
+    "main-horizontal-mirrored",
+    "main-vertical",
+    "main-vertical-mirrored",
+    "tiled",
+];
+
+/// Parses a `#{window_layout}` string. The checksum is optional and not verified, so hand-edited
+/// layouts can be read too. Returns `None` if the layout is malformed or its cells don't add up.
+pub(crate) fn parse(layout: &str) -> Option<LayoutCell> {
+    let mut parser = Parser {
+        input: split_checksum(layout).1.as_bytes(),
+        position: 0,
+    };
+    let cell = parser.cell()?;
+
+    (parser.at_end() && cell.is_consistent()).then_some(cell)
+}
+
+/// Serializes a layout the way tmux prints it, checksum included.
+pub(crate) fn serialize(cell: &LayoutCell) -> String {
+    let cells = cell.to_string();
+    format!("{:04x},{}", checksum(&cells), cells)
+}
+
+/// Same as tmux's `layout_checksum`.
+pub(crate) fn checksum(cells: &str) -> u16 {
+    cells
+        .bytes()
+        .fold(0, |sum: u16, c| sum.rotate_right(1).wrapping_add(c.into()))
+}
+
+/// Tells whether tmux would accept the layout and, if not, whether it can be fixed.
+pub(crate) fn check(layout: &str) -> Check {
+    if PRESETS.contains(&layout) {
+        return Check::Valid;
+    }
+
+    let Some(cell) = parse(layout) else {
+        return Check::Invalid;
+    };
+    let serialized = serialize(&cell);
+
+    if serialized == layout {
+        Check::Valid
+    } else {
+        Check::Repairable(serialized)
+    }
+}
+
+/// Whether two layouts arrange their panes the same way, regardless of pane ids and checksums.
+pub(crate) fn same_geometry(a: &str, b: &str) -> bool {
+    match (parse(a), parse(b)) {
+        (Some(a), Some(b)) => a.without_pane_ids() == b.without_pane_ids(),
+        _ => a == b,
+    }
+}
+
+/// Returns the checksum, if there is one, and the cells.
+fn split_checksum(layout: &str) -> (Option<&str>, &str) {
+    match layout.split_once(',') {
+        Some((checksum, cells))
+            if checksum.len() == 4 && checksum.chars().all(|c| c.is_ascii_hexdigit()) =>
+        {
+            (Some(checksum), cells)
+        }
+        _ => (None, layout),
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn cell(&mut self) -> Option<LayoutCell> {
+        let width = self.number()?;
+        self.expect(b'x')?;
+        let height = self.number()?;
+        self.expect(b',')?;
+        let x = self.number()?;
+        self.expect(b',')?;
+        let y = self.number()?;
+
+        let content = match self.peek() {
+            Some(b'{') => LayoutContent::Split(SplitDirection::Horizontal, self.children(b'}')?),
+            Some(b'[') => LayoutContent::Split(SplitDirection::Vertical, self.children(b']')?),
+            Some(b',') if self.is_pane_id() => {
+                self.position += 1;
+                LayoutContent::Pane(Some(self.number()?))
+            }
+            _ => LayoutContent::Pane(None),
+        };
+
+        Some(LayoutCell {
+            width,
+            height,
+            x,
+            y,
+            content,
+        })
+    }
+
+    fn children(&mut self, closing: u8) -> Option<Vec<LayoutCell>> {
+        self.position += 1;
+        let mut children = vec![self.cell()?];
+
+        while self.peek() == Some(b',') {
+            self.position += 1;
+            children.push(self.cell()?);
+        }
+
+        self.expect(closing)?;
+        Some(children)
+    }
+
+    /// A comma followed by a number which is not the start of the next cell (`<width>x...`).
+    fn is_pane_id(&self) -> bool {
+        let digits = self.input[self.position + 1..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        let next = self.input.get(self.position + 1 + digits);
+
+        digits > 0 && next != Some(&b'x')
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.position;
+
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+
+        std::str::from_utf8(&self.input[start..self.position])
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    fn expect(&mut self, c: u8) -> Option<()> {
+        (self.peek() == Some(c)).then(|| self.position += 1)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn at_end(&self) -> bool {
+        self.position == self.input.len()
+    }
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_a_single_pane() {
+        // When
+        let result = parse("a19d,50x20,0,0,0").unwrap();
+
+        // Then
+        assert_eq!(result.width, 50);
+        assert_eq!(result.height, 20);
+        assert_eq!(result.content, LayoutContent::Pane(Some(0)));
+    }
+
+    #[test]
+    fn should_parse_nested_splits() {
+        // When
+        let result =
+            parse("5c51,80x24,0,0{40x24,0,0,1,39x24,41,0[39x12,41,0,2,39x11,41,13,3]}").unwrap();
+
+        // Then
+        assert_eq!(result.pane_count(), 3);
+        let LayoutContent::Split(SplitDirection::Horizontal, children) = &result.content else {
+            panic!("Expected a horizontal split, got {:?}.", result.content);
+        };
+        assert_eq!(children[0].content, LayoutContent::Pane(Some(1)));
+        assert_eq!(children[1].x, 41);
+        let LayoutContent::Split(SplitDirection::Vertical, nested) = &children[1].content else {
+            panic!("Expected a vertical split, got {:?}.", children[1].content);
+        };
+        assert_eq!(nested[1].height, 11);
+        assert_eq!(nested[1].content, LayoutContent::Pane(Some(3)));
+    }
+
+    #[test]
+    fn should_parse_layouts_without_pane_ids() {
+        // When
+        let result = parse("bb62,159x48,0,0{79x48,0,0,79x48,80,0}").unwrap();
+
+        // Then
+        assert_eq!(result.pane_count(), 2);
+    }
+
+    #[test]
+    fn should_reject_malformed_layouts() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("main-vertical"), None);
+        assert_eq!(parse("a19d,50x20,0"), None);
+        assert_eq!(parse("a19d,80x24,0,0{40x24,0,0,1"), None);
+        assert_eq!(parse("a19d,50x20,0,0,0}"), None);
+    }
+
+    #[test]
+    fn should_reject_layouts_whose_cells_dont_add_up() {
+        assert_eq!(parse("0000,80x24,0,0{40x24,0,0,1,40x24,41,0,2}"), None);
+        assert_eq!(parse("0000,80x24,0,0[80x12,0,0,1,80x10,0,13,2]"), None);
+        assert_eq!(parse("0000,0x24,0,0,1"), None);
+    }
+}
+
+#[cfg(test)]
+mod check_tests {
+    use super::*;
+
+    #[test]
+    fn should_accept_layouts_printed_by_tmux() {
+        // Given
+        let layout = "4567,120x40,0,0{69x40,0,0[69x24,0,0,0,69x15,0,25,3],50x40,70,0\
+                      [50x29,70,0{25x29,70,0,1,24x29,96,0,4},50x10,70,30,2]}";
+
+        // When
+        let result = check(layout);
+
+        // Then
+        assert_eq!(result, Check::Valid);
+    }
+
+    #[test]
+    fn should_accept_preset_layouts() {
+        assert_eq!(check("main-vertical"), Check::Valid);
+        assert_eq!(check("tiled"), Check::Valid);
+    }
+
+    #[test]
+    fn should_repair_a_wrong_or_missing_checksum() {
+        assert_eq!(
+            check("ffff,50x20,0,0,0"),
+            Check::Repairable("a19d,50x20,0,0,0".to_string())
+        );
+        assert_eq!(
+            check("50x20,0,0,0"),
+            Check::Repairable("a19d,50x20,0,0,0".to_string())
+        );
+    }
+
+    #[test]
+    fn should_not_repair_malformed_layouts() {
+        assert_eq!(check("a19d,50x20,0,0{"), Check::Invalid);
+    }
+}
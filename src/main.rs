@@ -1,65 +1,105 @@
 mod args;
+mod autosave;
+mod bookmark_file;
 mod bookmarks;
 mod command_builder;
 mod config;
+mod diff;
+mod error;
+mod export;
+mod import;
+mod layout;
 mod model;
+mod output;
+mod process;
+mod projects;
 mod recent;
+mod scrollback;
 mod session;
 mod session_name_file;
 mod sessions;
+mod settings;
+mod snapshots;
+mod state_file;
 mod status;
 mod status_config;
+mod template;
+#[cfg(test)]
+mod test_utils;
 mod tmux;
 mod utils;
 mod window;
 mod workflow;
-use std::collections::HashSet;
+use std::{collections::HashSet, time::Duration};
 
 use args::{
-    Action, BookmarkAction, ConfigAction, ConfigPrintFilename, RecentSessionAction, SessionAction,
-    SessionsAction, WindowAction,
+    Action, BookmarkAction, BookmarkGroupAction, ConfigAction, ConfigPrintFilename, ExportFormat,
+    ImportFormat, RecentSessionAction, SessionAction, SessionsAction, SplitType, WindowAction,
 };
+use autosave::{Autosave, AutosaveImpl};
+use bookmark_file::{BookmarkFile, BookmarkFileImpl};
 use bookmarks::{Bookmarks, BookmarksImpl};
 use clap::Parser;
-use command_builder::CommandBuilderImpl;
+use command_builder::{CommandBuilder, CommandBuilderImpl, DryRunCommandBuilder};
 use config::Config;
+use import::{ProjectFile, ProjectFileImpl};
 use model::{TmuxPane, TmuxWindow};
+use projects::{Projects, ProjectsImpl};
 use recent::{Recent, RecentImpl};
+use scrollback::ScrollbackImpl;
 use session::{Session, SessionImpl};
 use session_name_file::{SessionNameFile, SessionNameFileImpl};
 use sessions::{SessionStorage, SessionStorageImpl};
+use settings::Settings;
+use snapshots::SnapshotsImpl;
 use status::{Status, StatusImpl};
 use status_config::StatusConfigFileImpl;
+use template::{Templates, TemplatesImpl};
 use tmux::{Tmux, TmuxImpl};
 use window::{Window, WindowImpl};
 use workflow::WorkflowImpl;
 
 use crate::{status_config::StatusConfigFile};
+use error::{Error, Result};
+
+fn run(config: &dyn Config, action: Action) -> Result<()> {
+    // `config print` doesn't need the settings, and has to work with a broken `config.toml`, so
+    // that it can be used to find the file.
+    let settings = match &action {
+        Action::Config { .. } => Settings::default(),
+        _ => config.settings()?,
+    };
 
-fn run(config: &dyn Config, action: Action) {
     match action {
         Action::Config { action } => match action {
-            ConfigAction::Print { action } => match action {
-                ConfigPrintFilename::Sessions => {
-                    eprintln!("{}", config.sessions_filename());
-                }
-                ConfigPrintFilename::RecentSessions => {
-                    eprintln!("{}", config.recent_sessions_filename());
-                }
-                ConfigPrintFilename::Bookmarks => {
-                    eprintln!("{}", config.bookmarks_filename());
-                }
-            },
+            ConfigAction::Print { action, output } => {
+                let filename = match action {
+                    ConfigPrintFilename::Sessions => config.sessions_filename(),
+                    ConfigPrintFilename::RecentSessions => config.recent_sessions_filename(),
+                    ConfigPrintFilename::Bookmarks => config.bookmarks_filename(),
+                    ConfigPrintFilename::Templates => config.templates_filename(),
+                };
+
+                output::print(
+                    output.format.as_ref(),
+                    &filename,
+                    std::slice::from_ref(&filename),
+                )?;
+            }
         },
         Action::Session { action } => match action {
             SessionAction::FindAll => {
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
-                let sessions =
-                    SessionStorageImpl::new(config.sessions_filename().as_str(), &tmux).load();
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let sessions = SessionStorageImpl::new(
+                    config.sessions_filename().as_str(),
+                    &tmux,
+                    &settings.restore,
+                )
+                .load()?;
                 let recent_sessions: &dyn SessionNameFile =
                     &SessionNameFileImpl::new(config.recent_sessions_filename().as_str());
-                let saved_session_names: Vec<String> = sessions.into_keys().collect();
-                let recent_session_names: Vec<String> = recent_sessions.read();
+                let saved_session_names: Vec<String> = sessions.keys().cloned().collect();
+                let recent_session_names: Vec<String> = recent_sessions.read()?;
                 let session = SessionImpl::new(&tmux);
                 let unique_session_names: HashSet<String> = session
                     .list_names()
@@ -69,9 +109,9 @@ fn run(config: &dyn Config, action: Action) {
                 let mut stored_names: Vec<String> = unique_session_names.into_iter().collect();
                 let compare = |a: &String, b: &String| a.to_lowercase().cmp(&b.to_lowercase());
                 stored_names.sort_by(compare);
-                let current_session_name = tmux.current_session_name();
+                let current_session_name = tmux.current_session_name()?;
 
-                let session_names: Vec<String> = recent_session_names
+                let mut session_names: Vec<String> = recent_session_names
                     .iter()
                     .chain(
                         stored_names
@@ -81,51 +121,243 @@ fn run(config: &dyn Config, action: Action) {
                     .map(|name| name.to_string())
                     .filter(|s| s != &current_session_name)
                     .collect();
+                session_names.extend(
+                    ProjectsImpl::new(&settings.projects)
+                        .discover()
+                        .iter()
+                        .filter(|p| {
+                            let name = projects::project_session_name(&p.path, |name| {
+                                session.pane_paths(name, &sessions)
+                            });
+                            !stored_names.contains(&name) && name != current_session_name
+                        })
+                        .map(projects::picker_entry),
+                );
 
                 if session_names.is_empty() {
-                    tmux.display_message("No other sessions found.");
-                    return;
+                    tmux.display_message("No other sessions found.")?;
+                    return Ok(());
                 }
 
                 let session = SessionImpl::new(&tmux);
-                session.find(session_names, None);
+                session.find(session_names, None, &settings.picker)?;
             }
             SessionAction::Find => {
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
                 let session = SessionImpl::new(&tmux);
-                let session_names = session.list_other_session_names();
+                let session_names = session.list_other_session_names()?;
 
                 if session_names.is_empty() {
-                    run(
+                    return run(
                         config,
                         Action::Session {
                             action: SessionAction::FindAll,
                         },
                     );
-                    return;
                 }
 
                 let session = SessionImpl::new(&tmux);
-                session.find(session_names, Some("Sessions".to_string()));
+                session.find(
+                    session_names,
+                    Some("Sessions".to_string()),
+                    &settings.picker,
+                )?;
             }
-            SessionAction::Select { session_name } => {
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
+            SessionAction::Select {
+                session_name,
+                dry_run,
+            } => {
+                if dry_run {
+                    select_session(
+                        &DryRunCommandBuilder::new(&CommandBuilderImpl),
+                        config,
+                        &settings,
+                        &session_name,
+                    )?;
+                } else {
+                    select_session(&CommandBuilderImpl, config, &settings, &session_name)?;
+                }
+            }
+            SessionAction::Save { scrollback } => {
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
                 let session = SessionImpl::new(&tmux);
-                let sessions = SessionStorageImpl::new(config.sessions_filename().as_str(), &tmux);
+                let sessions = SessionStorageImpl::new(
+                    config.sessions_filename().as_str(),
+                    &tmux,
+                    &settings.restore,
+                );
+                session.save(&tmux.current_session_name()?, &sessions, &settings.save)?;
 
-                session.select(session_name.as_str(), &sessions);
+                if scrollback {
+                    session.save_scrollback(
+                        &tmux.current_session_name()?,
+                        &ScrollbackImpl::new(&config.sessions_filename()),
+                        settings.scrollback.max_bytes_per_session,
+                    )?;
+                }
             }
-            SessionAction::Save => {
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
+            SessionAction::Delete { session_name } => {
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
                 let session = SessionImpl::new(&tmux);
-                let sessions = SessionStorageImpl::new(config.sessions_filename().as_str(), &tmux);
-                session.save(&sessions);
+                let sessions =
+                    SessionStorageImpl::new(&config.sessions_filename(), &tmux, &settings.restore);
+                session.delete(&session_name, &sessions)?;
             }
-            SessionAction::Delete { session_name } => {
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
+            SessionAction::Kill {
+                session_name,
+                save,
+                forget,
+            } => {
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let session = SessionImpl::new(&tmux);
+                let sessions =
+                    SessionStorageImpl::new(&config.sessions_filename(), &tmux, &settings.restore);
+                let file = SessionNameFileImpl::new(config.recent_sessions_filename().as_str());
+                let recent = RecentImpl::new(&tmux, &file);
+                let session_name = match session_name {
+                    Some(session_name) => session_name,
+                    None => tmux.current_session_name()?,
+                };
+
+                session.kill(
+                    &session_name,
+                    save.then_some(&settings.save),
+                    forget,
+                    &sessions,
+                    &recent,
+                )?;
+            }
+            SessionAction::Rename {
+                session_name,
+                new_name,
+            } => {
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
                 let session = SessionImpl::new(&tmux);
-                let sessions = SessionStorageImpl::new(&config.sessions_filename(), &tmux);
-                session.delete(&session_name, &sessions);
+                let sessions =
+                    SessionStorageImpl::new(&config.sessions_filename(), &tmux, &settings.restore);
+                let scrollback = ScrollbackImpl::new(&config.sessions_filename());
+                let recent_file = SessionNameFileImpl::new(&config.recent_sessions_filename());
+                let bookmarks_file = BookmarkFileImpl::new(&config.bookmarks_filename());
+
+                let new_name = session.rename(
+                    &session_name,
+                    &new_name,
+                    &sessions,
+                    &scrollback,
+                    &[&recent_file],
+                )?;
+                bookmarks_file.rename(&session_name, &new_name)?;
+
+                if tmux.has_session(&new_name)? {
+                    tmux.refresh_status()?;
+                }
+            }
+            SessionAction::Clone {
+                session_name,
+                new_name,
+                path_rewrites,
+                start,
+            } => {
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let session = SessionImpl::new(&tmux);
+                let sessions =
+                    SessionStorageImpl::new(&config.sessions_filename(), &tmux, &settings.restore);
+
+                let new_name = session.clone(
+                    &session_name,
+                    &new_name,
+                    &path_rewrites,
+                    &sessions,
+                    &settings.save,
+                )?;
+
+                if start {
+                    session.select(&new_name, &sessions)?;
+                }
+            }
+            SessionAction::New {
+                template,
+                name,
+                path,
+                variables,
+                start,
+            } => {
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let session = SessionImpl::new(&tmux);
+                let sessions =
+                    SessionStorageImpl::new(&config.sessions_filename(), &tmux, &settings.restore);
+                let name = utils::valid_session_name(&name);
+
+                if sessions.load()?.contains_key(&name) || tmux.has_session(&name)? {
+                    return Err(Error::AlreadyExists(format!(
+                        "Session '{}' already exists.",
+                        name
+                    )));
+                }
+
+                let path = match path {
+                    Some(path) => path,
+                    None => std::env::current_dir()
+                        .map_err(Error::io("."))?
+                        .to_string_lossy()
+                        .to_string(),
+                };
+                let mut all_variables = template::builtin_variables(&name, &path);
+                all_variables.extend(variables);
+
+                let templates = TemplatesImpl::new(&config.templates_filename());
+                let new_session = templates.instantiate(&template, &all_variables)?;
+                session.update(&name, new_session, &sessions)?;
+
+                if start {
+                    session.select(&name, &sessions)?;
+                }
+            }
+            SessionAction::OpenProject { path } => {
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let session = SessionImpl::new(&tmux);
+                let sessions =
+                    SessionStorageImpl::new(&config.sessions_filename(), &tmux, &settings.restore);
+                let path = projects::expand_home(&path);
+                let stored_sessions = sessions.load()?;
+                let name = projects::project_session_name(&path, |name| {
+                    session.pane_paths(name, &stored_sessions)
+                });
+
+                if !stored_sessions.contains_key(&name) && !session.list_names().contains(&name) {
+                    let new_session = match &settings.projects.template {
+                        Some(template) => TemplatesImpl::new(&config.templates_filename())
+                            .instantiate(template, &template::builtin_variables(&name, &path))?,
+                        None => projects::default_session(&projects::Project {
+                            name: name.clone(),
+                            path,
+                        }),
+                    };
+                    session.update(&name, new_session, &sessions)?;
+                }
+
+                session.select(&name, &sessions)?;
+            }
+            SessionAction::ApplyTemplate { session_name } => {
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let session = SessionImpl::new(&tmux);
+                let sessions =
+                    SessionStorageImpl::new(&config.sessions_filename(), &tmux, &settings.restore);
+
+                let Some(link) = sessions
+                    .load()?
+                    .remove(&session_name)
+                    .and_then(|s| s.template)
+                else {
+                    return Err(Error::NotFound(format!(
+                        "Session '{}' was not created from a template.",
+                        session_name
+                    )));
+                };
+
+                let templates = TemplatesImpl::new(&config.templates_filename());
+                let new_session = templates.instantiate(&link.name, &link.variables)?;
+                session.update(&session_name, new_session, &sessions)?;
             }
             SessionAction::Update {
                 session_name,
@@ -136,11 +368,12 @@ fn run(config: &dyn Config, action: Action) {
                 startup_command,
                 shell_command,
             } => {
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
                 let session = SessionImpl::new(&tmux);
-                let sessions = SessionStorageImpl::new(&config.sessions_filename(), &tmux);
+                let sessions =
+                    SessionStorageImpl::new(&config.sessions_filename(), &tmux, &settings.restore);
 
-                if let Some(s) = sessions.load().get(&session_name) {
+                if let Some(s) = sessions.load()?.get(&session_name) {
                     let mut sess = s.clone();
 
                     if background {
@@ -151,8 +384,8 @@ fn run(config: &dyn Config, action: Action) {
                         sess.no_recent_tracking = Some(true);
                     }
 
-                    let window_index = tmux.current_window_index();
-                    let pane_index = tmux.current_window_index();
+                    let window_index = tmux.current_window_index()?;
+                    let pane_index = tmux.current_window_index()?;
 
                     if window_active {
                         for window in sess.windows.iter_mut() {
@@ -193,187 +426,706 @@ fn run(config: &dyn Config, action: Action) {
                     }
 
                     eprintln!("{:?}", sess);
-                    session.update(&session_name, sess, &sessions);
+                    session.update(&session_name, sess, &sessions)?;
                 }
             }
         },
         Action::Sessions { action } => match action {
-            SessionsAction::Save { filename } => {
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
+            SessionsAction::Save {
+                filename,
+                scrollback,
+            } => {
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
                 let file = filename.unwrap_or(config.sessions_filename());
-                let sessions = SessionStorageImpl::new(&file, &tmux);
-                let stored_sessions = sessions.load();
+                let sessions = SessionStorageImpl::new(&file, &tmux, &settings.restore);
                 let session = SessionImpl::new(&tmux);
-                let current_sessions = session.list();
-                let merged_sessions = utils::merge(stored_sessions, current_sessions);
+                let current_sessions = session.list(&settings.save)?;
 
-                sessions.save(merged_sessions);
+                if scrollback {
+                    let scrollback = ScrollbackImpl::new(&file);
+
+                    for name in current_sessions.keys() {
+                        session.save_scrollback(
+                            name,
+                            &scrollback,
+                            settings.scrollback.max_bytes_per_session,
+                        )?;
+                    }
+                }
+
+                sessions.modify(&mut |stored_sessions| {
+                    let stored = std::mem::take(stored_sessions);
+                    *stored_sessions = utils::merge(stored, current_sessions.clone());
+                })?;
             }
 
-            SessionsAction::Restore { filename } => {
+            SessionsAction::Restore { filename, dry_run } => {
                 let file = filename.unwrap_or(config.sessions_filename());
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
-                let sessions = SessionStorageImpl::new(&file, &tmux);
-                sessions.restore_all();
+
+                if dry_run {
+                    restore_sessions(
+                        &DryRunCommandBuilder::new(&CommandBuilderImpl),
+                        &file,
+                        &settings,
+                    )?;
+                } else {
+                    restore_sessions(&CommandBuilderImpl, &file, &settings)?;
+                }
             }
-            SessionsAction::List => {
+            SessionsAction::Autosave {
+                interval,
+                keep,
+                once,
+            } => {
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let sessions =
+                    SessionStorageImpl::new(&config.sessions_filename(), &tmux, &settings.restore);
+                let snapshots = SnapshotsImpl::new(&config.snapshots_dirname(), "sessions");
+                let autosave = AutosaveImpl::new(&tmux, &sessions, &snapshots, &settings.save);
+                let keep = keep.unwrap_or(settings.autosave.keep);
+
+                if once {
+                    autosave.snapshot(keep)?;
+                } else {
+                    let interval = interval.unwrap_or(settings.autosave.interval_secs);
+                    autosave.run(Duration::from_secs(interval), keep)?;
+                }
+            }
+            SessionsAction::List { output } => {
+                let file = config.sessions_filename();
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let sessions = SessionStorageImpl::new(&file, &tmux, &settings.restore);
+                let live_names = SessionImpl::new(&tmux).list_names();
+                let recent_names =
+                    SessionNameFileImpl::new(&config.recent_sessions_filename()).read()?;
+                let bookmarked_names = BookmarkFileImpl::new(&config.bookmarks_filename())
+                    .read()?
+                    .into_iter()
+                    .map(|bookmark| bookmark.session)
+                    .collect::<Vec<_>>();
+                let summaries = sessions::summaries(
+                    sessions.load()?,
+                    &live_names,
+                    &bookmarked_names,
+                    &recent_names,
+                );
+                let names = summaries
+                    .iter()
+                    .map(|summary| summary.name.clone())
+                    .collect::<Vec<_>>();
+
+                output::print(output.format.as_ref(), &summaries, &names)?;
+            }
+            SessionsAction::History => {
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let sessions =
+                    SessionStorageImpl::new(&config.sessions_filename(), &tmux, &settings.restore);
+
+                for version in sessions.history()? {
+                    let changes = &version.changes;
+                    let summary = changes
+                        .added
+                        .iter()
+                        .map(|name| format!("+{}", name))
+                        .chain(changes.removed.iter().map(|name| format!("-{}", name)))
+                        .chain(changes.changed.iter().map(|name| format!("~{}", name)))
+                        .collect::<Vec<String>>()
+                        .join(" ");
+
+                    eprintln!(
+                        "{:3}: {}  {}",
+                        version.number,
+                        utils::format_timestamp(version.timestamp),
+                        if summary.is_empty() {
+                            "no changes"
+                        } else {
+                            &summary
+                        }
+                    );
+                }
+            }
+            SessionsAction::Rollback { version } => {
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let sessions =
+                    SessionStorageImpl::new(&config.sessions_filename(), &tmux, &settings.restore);
+
+                if !sessions.rollback(version)? {
+                    return Err(Error::NotFound(format!("Version {} not found.", version)));
+                }
+            }
+            SessionsAction::Check { fix } => {
                 let file = config.sessions_filename();
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
-                let sessions = SessionStorageImpl::new(&file, &tmux);
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let sessions = SessionStorageImpl::new(&file, &tmux, &settings.restore);
+                let mut unfixed = 0;
+
+                for layout in sessions.check_layouts(fix)? {
+                    let problem = match (&layout.repaired, fix) {
+                        (Some(_), true) => "checksum repaired",
+                        (Some(_), false) => "wrong checksum, repair with --fix",
+                        (None, _) => "invalid layout",
+                    };
 
-                for session_name in sessions.list() {
-                    eprintln!("{}", session_name);
+                    if layout.repaired.is_none() || !fix {
+                        unfixed += 1;
+                    }
+
+                    eprintln!(
+                        "{}:{}: {}",
+                        layout.session_name, layout.window_name, problem
+                    );
+                }
+
+                if unfixed > 0 {
+                    return Err(Error::parse(
+                        file,
+                        format!("{} window layout(s) would be rejected by tmux.", unfixed),
+                    ));
                 }
             }
+            SessionsAction::Import { format, filename } => {
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let sessions =
+                    SessionStorageImpl::new(&config.sessions_filename(), &tmux, &settings.restore);
+                let imported = ProjectFileImpl::new(&filename, &settings.save).import(&format)?;
+
+                for feature in &imported.unmapped {
+                    eprintln!("{}: {} not imported", filename, feature);
+                }
+
+                // A project file describes a single session, which is refused like in `session
+                // new`. A resurrect file holds every session, so the stored ones are skipped.
+                if !matches!(format, ImportFormat::Resurrect) {
+                    let stored_sessions = sessions.load()?;
+
+                    if let Some(name) = imported
+                        .sessions
+                        .keys()
+                        .find(|name| stored_sessions.contains_key(*name))
+                    {
+                        return Err(Error::AlreadyExists(format!(
+                            "Session '{}' already exists.",
+                            name
+                        )));
+                    }
+                }
+
+                let mut added = vec![];
+                let mut skipped = vec![];
+                sessions.modify(&mut |stored_sessions| {
+                    added.clear();
+                    skipped.clear();
+
+                    for (name, session) in &imported.sessions {
+                        if stored_sessions.contains_key(name) {
+                            skipped.push(name.clone());
+                        } else {
+                            stored_sessions.insert(name.clone(), session.clone());
+                            added.push(name.clone());
+                        }
+                    }
+                })?;
+                added.sort();
+                skipped.sort();
+
+                for name in &skipped {
+                    eprintln!("Session '{}' already exists, not imported.", name);
+                }
+
+                if !added.is_empty() {
+                    eprintln!(
+                        "Imported {} into {}.",
+                        added
+                            .iter()
+                            .map(|name| format!("'{}'", name))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        config.sessions_filename()
+                    );
+                }
+            }
+            SessionsAction::Diff {
+                session_name,
+                output,
+            } => {
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let stored =
+                    SessionStorageImpl::new(&config.sessions_filename(), &tmux, &settings.restore)
+                        .load()?;
+                let live = SessionImpl::new(&tmux).list(&settings.save)?;
+                let session_names = match session_name {
+                    Some(name) if stored.contains_key(&name) || live.contains_key(&name) => {
+                        vec![name]
+                    }
+                    Some(name) => {
+                        return Err(Error::NotFound(format!("Session '{}' not found.", name)));
+                    }
+                    None => {
+                        let mut names = stored
+                            .keys()
+                            .chain(live.keys())
+                            .cloned()
+                            .collect::<HashSet<_>>()
+                            .into_iter()
+                            .collect::<Vec<_>>();
+                        names.sort_by_key(|name| name.to_lowercase());
+                        names
+                    }
+                };
+                let diffs = session_names
+                    .into_iter()
+                    .map(|name| diff::SessionDiff {
+                        changes: diff::diff(stored.get(&name), live.get(&name)),
+                        session_name: name,
+                    })
+                    .filter(|session_diff| !session_diff.changes.is_empty())
+                    .collect::<Vec<_>>();
+                let lines = diffs
+                    .iter()
+                    .flat_map(|session_diff| {
+                        session_diff
+                            .changes
+                            .iter()
+                            .map(|change| format!("{}: {}", session_diff.session_name, change))
+                    })
+                    .collect::<Vec<_>>();
+
+                output::print(output.format.as_ref(), &diffs, &lines)?;
+            }
+            SessionsAction::Export {
+                format,
+                session_name,
+            } => match format {
+                ExportFormat::Sh => print!(
+                    "{}",
+                    export::shell_script(
+                        &config.sessions_filename(),
+                        &session_name,
+                        &settings.restore,
+                        &settings.environment,
+                    )?
+                ),
+            },
             SessionsAction::Convert { filename } => {
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
-                let sessions = SessionStorageImpl::new(&config.sessions_filename(), &tmux);
-                sessions.convert(&filename);
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let sessions =
+                    SessionStorageImpl::new(&config.sessions_filename(), &tmux, &settings.restore);
+                sessions.convert(&filename)?;
             }
         },
         Action::RecentSession { action } => match action {
-            RecentSessionAction::List => {
+            RecentSessionAction::List { output } => {
                 let file = SessionNameFileImpl::new(config.recent_sessions_filename().as_str());
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
                 let recent = RecentImpl::new(&tmux, &file);
 
-                recent.print()
+                recent.print(output.format.as_ref())?
             }
             RecentSessionAction::Next => {
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
                 let file = SessionNameFileImpl::new(config.recent_sessions_filename().as_str());
                 let recent = RecentImpl::new(&tmux, &file);
 
-                if let Some(name) = recent.next(&tmux.current_session_name()) {
+                if let Some(name) = recent.next(&tmux.current_session_name()?)? {
                     let session = SessionImpl::new(&tmux);
                     session.select(
                         &name,
-                        &SessionStorageImpl::new(config.sessions_filename().as_str(), &tmux),
-                    );
+                        &SessionStorageImpl::new(
+                            config.sessions_filename().as_str(),
+                            &tmux,
+                            &settings.restore,
+                        ),
+                    )?;
                 }
             }
             RecentSessionAction::Previous => {
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
                 let file = SessionNameFileImpl::new(config.recent_sessions_filename().as_str());
                 let recent = RecentImpl::new(&tmux, &file);
 
-                if let Some(name) = recent.previous(&tmux.current_session_name()) {
+                if let Some(name) = recent.previous(&tmux.current_session_name()?)? {
                     let session = SessionImpl::new(&tmux);
                     session.select(
                         &name,
-                        &SessionStorageImpl::new(config.sessions_filename().as_str(), &tmux),
-                    );
+                        &SessionStorageImpl::new(
+                            config.sessions_filename().as_str(),
+                            &tmux,
+                            &settings.restore,
+                        ),
+                    )?;
                 }
             }
             RecentSessionAction::Edit => {
                 let file = SessionNameFileImpl::new(config.recent_sessions_filename().as_str());
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
                 let recent = RecentImpl::new(&tmux, &file);
 
-                recent.edit(config);
+                recent.edit(config, &settings)?;
             }
             RecentSessionAction::Add { session_name } => {
-                let tmux = &TmuxImpl::new(&CommandBuilderImpl);
-                let name = session_name.unwrap_or(tmux.current_session_name());
+                let tmux = &TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let name = session_name.unwrap_or(tmux.current_session_name()?);
                 let recent_file =
                     SessionNameFileImpl::new(config.recent_sessions_filename().as_str());
                 let recent = RecentImpl::new(tmux, &recent_file);
                 let sessions_file = config.sessions_filename();
-                let sessions =
-                    SessionStorageImpl::new(&sessions_file, &TmuxImpl::new(&CommandBuilderImpl))
-                        .load();
+                let sessions = SessionStorageImpl::new(
+                    &sessions_file,
+                    &TmuxImpl::new(&CommandBuilderImpl, &settings.environment),
+                    &settings.restore,
+                )
+                .load()?;
                 let session = sessions.get(&name);
 
-                recent.add(session, &name);
+                recent.add(session, &name)?;
             }
         },
         Action::Bookmark { action } => match action {
-            BookmarkAction::List => {
-                let file = SessionNameFileImpl::new(config.bookmarks_filename().as_str());
+            BookmarkAction::List { output } => {
+                let file = BookmarkFileImpl::new(config.bookmarks_filename().as_str());
                 let bookmarks = BookmarksImpl::new(&file);
 
-                bookmarks.print();
+                bookmarks.print(output.format.as_ref())?;
             }
             BookmarkAction::Set => {
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
-                let file = SessionNameFileImpl::new(config.bookmarks_filename().as_str());
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let file = BookmarkFileImpl::new(config.bookmarks_filename().as_str());
                 let bookmarks = BookmarksImpl::new(&file);
 
-                if bookmarks.set(&tmux) {
-                    run(config, Action::Status { theme: None });
+                if bookmarks.set(&tmux)? {
+                    run(config, Action::Status { theme: None })?;
                 }
             }
-            BookmarkAction::Select { index, smart_focus } => {
-                let file = SessionNameFileImpl::new(config.bookmarks_filename().as_str());
+            BookmarkAction::Select { slot, smart_focus } => {
+                let file = BookmarkFileImpl::new(config.bookmarks_filename().as_str());
                 let bookmarks = BookmarksImpl::new(&file);
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
-                let current_session_name = tmux.current_session_name();
+                let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+                let current_session_name = tmux.current_session_name()?;
 
-                if let Some(name) = bookmarks.select(index) {
+                if let Some(name) = bookmarks.select(slot)? {
                     if name == current_session_name {
                         if let Some(smart_focus) = smart_focus {
-                            tmux.select_window(&name, smart_focus);
-                            return;
+                            tmux.select_window(&name, smart_focus)?;
+                            return Ok(());
                         }
 
-                        return;
+                        return Ok(());
                     }
 
                     let session = SessionImpl::new(&tmux);
-                    let sessions =
-                        SessionStorageImpl::new(config.sessions_filename().as_str(), &tmux);
+                    let sessions = SessionStorageImpl::new(
+                        config.sessions_filename().as_str(),
+                        &tmux,
+                        &settings.restore,
+                    );
 
-                    session.select(&name, &sessions);
+                    session.select(&name, &sessions)?;
                 }
             }
             BookmarkAction::Edit => {
-                let file = SessionNameFileImpl::new(config.bookmarks_filename().as_str());
+                let file = BookmarkFileImpl::new(config.bookmarks_filename().as_str());
+                let bookmarks = BookmarksImpl::new(&file);
+
+                bookmarks.edit(
+                    config,
+                    &settings,
+                    &TmuxImpl::new(&CommandBuilderImpl, &settings.environment),
+                )?;
+                run(config, Action::Status { theme: None })?;
+            }
+            BookmarkAction::Group { action } => {
+                let file = BookmarkFileImpl::new(config.bookmarks_filename().as_str());
                 let bookmarks = BookmarksImpl::new(&file);
 
-                bookmarks.edit(config, &TmuxImpl::new(&CommandBuilderImpl));
-                run(config, Action::Status { theme: None });
+                match action {
+                    BookmarkGroupAction::List { output } => {
+                        bookmarks.print_groups(output.format.as_ref())?
+                    }
+                    BookmarkGroupAction::Use { name } => {
+                        bookmarks.use_group(&name)?;
+                        run(config, Action::Status { theme: None })?;
+                    }
+                }
             }
         },
         Action::Status { theme } => {
-            let tmux = &TmuxImpl::new(&CommandBuilderImpl);
-            let session_file = SessionNameFileImpl::new(config.bookmarks_filename().as_str());
+            let tmux = &TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
+            let session_file = BookmarkFileImpl::new(config.bookmarks_filename().as_str());
             let status_filename = match theme {
                 Some(filename) => filename,
                 None => config.status_config_filename().as_str().to_owned(),
             };
             let status_file = StatusConfigFileImpl::new(&status_filename);
-            let status_config = status_file.load();
+            let status_config = status_file.load()?;
             let status = StatusImpl::new(tmux, &session_file, &status_config);
-            status.set();
+            status.set()?;
         }
         Action::Window { action } => match action {
             WindowAction::SmartSplit {
                 split_type,
                 session_name,
+                dry_run,
             } => {
-                let tmux = TmuxImpl::new(&CommandBuilderImpl);
-                let sessions = SessionStorageImpl::new(config.sessions_filename().as_str(), &tmux);
-
-                if let Some(session) = sessions.load().get(&session_name) {
-                    let window = WindowImpl::new(&tmux);
-                    window.smart_split(&session_name, session, &split_type);
+                if dry_run {
+                    smart_split(
+                        &DryRunCommandBuilder::new(&CommandBuilderImpl),
+                        config,
+                        &settings,
+                        &session_name,
+                        &split_type,
+                    )?;
                 } else {
-                    eprintln!("Session '{}' not found.", session_name);
+                    smart_split(
+                        &CommandBuilderImpl,
+                        config,
+                        &settings,
+                        &session_name,
+                        &split_type,
+                    )?;
                 }
             }
         },
         Action::Workflow { key } => {
-            let tmux = TmuxImpl::new(&CommandBuilderImpl);
+            let tmux = TmuxImpl::new(&CommandBuilderImpl, &settings.environment);
             let workflow = WorkflowImpl::new(&tmux);
-            workflow.on_action(key.as_str());
+            workflow.on_action(key.as_str())?;
         }
     }
+
+    Ok(())
+}
+
+// The commands supporting `--dry-run` are generic over the command builder, which is either the
+// real one or `DryRunCommandBuilder`.
+fn select_session(
+    command_builder: &impl CommandBuilder,
+    config: &dyn Config,
+    settings: &Settings,
+    session_name: &str,
+) -> Result<()> {
+    let tmux = TmuxImpl::new(command_builder, &settings.environment);
+    let session = SessionImpl::new(&tmux);
+    let sessions = SessionStorageImpl::new(
+        config.sessions_filename().as_str(),
+        &tmux,
+        &settings.restore,
+    );
+
+    session.select(session_name, &sessions)
+}
+
+fn restore_sessions(
+    command_builder: &impl CommandBuilder,
+    filename: &str,
+    settings: &Settings,
+) -> Result<()> {
+    let tmux = TmuxImpl::new(command_builder, &settings.environment);
+    let sessions = SessionStorageImpl::new(filename, &tmux, &settings.restore);
+    sessions.restore_all()
+}
+
+fn smart_split(
+    command_builder: &impl CommandBuilder,
+    config: &dyn Config,
+    settings: &Settings,
+    session_name: &str,
+    split_type: &SplitType,
+) -> Result<()> {
+    let tmux = TmuxImpl::new(command_builder, &settings.environment);
+    let sessions = SessionStorageImpl::new(
+        config.sessions_filename().as_str(),
+        &tmux,
+        &settings.restore,
+    );
+
+    let stored_sessions = sessions.load()?;
+    let Some(session) = stored_sessions.get(session_name) else {
+        return Err(Error::NotFound(format!(
+            "Session '{}' not found.",
+            session_name
+        )));
+    };
+
+    WindowImpl::new(&tmux).smart_split(session_name, session, split_type)
+}
+
+/// Reports the error on stderr and, when running inside tmux (e.g. from a keybinding), also in the
+/// tmux client, where stderr is not visible.
+fn report(error: &Error) {
+    eprintln!("stmux: {}", error);
+
+    if std::env::var_os("TMUX").is_some() {
+        let tmux = TmuxImpl::new(&CommandBuilderImpl, &[]);
+        let _ = tmux.display_message(&format!("stmux: {}", error));
+    }
 }
 
 fn main() {
     let args = args::Args::parse();
     let config = config::ConfigImpl;
-    config.create_dir();
 
-    run(&config, args.action);
+    if let Err(error) = config.create_dir().and_then(|_| run(&config, args.action)) {
+        report(&error);
+        std::process::exit(error.exit_code());
+    }
+}
+
+#[cfg(test)]
+mod dry_run_tests {
+    use std::{
+        collections::HashMap,
+        io,
+        os::unix::process::ExitStatusExt,
+        process::{Command, ExitStatus, Output},
+    };
+
+    use super::*;
+    use crate::{config::MockConfig, settings::RestoreSettings, test_utils::TempDir};
+
+    /// Answers queries with fixed outputs, keyed by the joined arguments. Any other query fails,
+    /// e.g. `has-session` for a session that isn't running.
+    struct FakeServer {
+        outputs: HashMap<&'static str, &'static str>,
+    }
+
+    impl FakeServer {
+        fn new(outputs: &[(&'static str, &'static str)]) -> Self {
+            Self {
+                outputs: outputs.iter().cloned().collect(),
+            }
+        }
+
+        fn answer(&self, command: &Command) -> Option<&str> {
+            let args = command
+                .get_args()
+                .map(|arg| arg.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            self.outputs.get(args.as_str()).copied()
+        }
+    }
+
+    impl CommandBuilder for FakeServer {
+        fn new_command(&self) -> Command {
+            Command::new("tmux")
+        }
+
+        fn status(&self, command: &mut Command) -> io::Result<ExitStatus> {
+            Ok(self.output(command)?.status)
+        }
+
+        fn output(&self, command: &mut Command) -> io::Result<Output> {
+            let answer = self.answer(command);
+
+            Ok(Output {
+                status: ExitStatus::from_raw(if answer.is_some() { 0 } else { 256 }),
+                stdout: answer.unwrap_or_default().as_bytes().to_vec(),
+                stderr: vec![],
+            })
+        }
+    }
+
+    const SESSIONS: &str = r#"
+[work]
+windows = [
+    { index = 1, name = "code", layout = "020a,80x24,0,0{40x24,0,0,1,39x24,41,0,2}", options = [], panes = [
+        { index = 1, path = "/src", active = true },
+        { index = 2, path = "/tmp", active = false },
+    ] },
+]
+"#;
+
+    const RESTORE_COMMANDS: [&str; 5] = [
+        "pane_1=$(tmux new-session -d -P -F '#{pane_id}' -s work -n code -x 80 -y 24 -e NO_CD=1 -c /src)",
+        "pane_2=$(tmux split-window -P -F '#{pane_id}' -h -t \"$pane_1\" -l 39 -c /tmp -e NO_CD=1)",
+        "tmux select-pane -t \"$pane_1\"",
+        "tmux select-window -t \"$pane_1\"",
+        "tmux select-layout -t work:code '020a,80x24,0,0{40x24,0,0,1,39x24,41,0,2}'",
+    ];
+
+    fn settings() -> Settings {
+        Settings {
+            restore: RestoreSettings {
+                layout_delay_millis: 0,
+            },
+            ..Settings::default()
+        }
+    }
+
+    fn config(directory: &TempDir) -> MockConfig {
+        let filename = directory.filename("sessions.toml");
+        std::fs::write(&filename, SESSIONS).unwrap();
+        let mut config = MockConfig::new();
+        config
+            .expect_sessions_filename()
+            .returning(move || filename.clone());
+        config
+    }
+
+    #[test]
+    fn should_print_the_commands_restoring_sessions() {
+        // Given
+        let directory = TempDir::new();
+        let config = config(&directory);
+        let server = FakeServer::new(&[]);
+        let command_builder = DryRunCommandBuilder::new(&server);
+
+        // When
+        let result = restore_sessions(&command_builder, &config.sessions_filename(), &settings());
+
+        // Then
+        assert!(result.is_ok());
+        assert_eq!(command_builder.lines(), RESTORE_COMMANDS);
+    }
+
+    #[test]
+    fn should_print_the_commands_selecting_a_stored_session() {
+        // Given
+        let directory = TempDir::new();
+        let config = config(&directory);
+        let server = FakeServer::new(&[]);
+        let command_builder = DryRunCommandBuilder::new(&server);
+
+        // When
+        let result = select_session(&command_builder, &config, &settings(), "work");
+
+        // Then
+        assert!(result.is_ok());
+        let mut expected = RESTORE_COMMANDS.to_vec();
+        expected.push("tmux switch-client -t work");
+        assert_eq!(command_builder.lines(), expected);
+    }
+
+    #[test]
+    fn should_count_the_printed_split_when_naming_the_new_pane() {
+        // Given
+        let directory = TempDir::new();
+        let config = config(&directory);
+        let server = FakeServer::new(&[
+            ("list-panes -F #{pane_index}:#{@window-name}", "1:\n"),
+            ("list-windows -F #W", "shell\n"),
+            ("display-message -p #{window_panes}", "1\n"),
+        ]);
+        let command_builder = DryRunCommandBuilder::new(&server);
+
+        // When
+        let result = smart_split(
+            &command_builder,
+            &config,
+            &settings(),
+            "work",
+            &SplitType::Right,
+        );
+
+        // Then
+        assert!(result.is_ok());
+        assert_eq!(
+            command_builder.lines(),
+            [
+                "pane_1=$(tmux split-window -P -F '#{pane_id}' -h -c /src -e NO_CD=1)",
+                "tmux set -p -t .2 @window-name work",
+            ]
+        );
+    }
 }
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub(crate) struct NameValue {
     pub(crate) name: String,
     pub(crate) value: String,
@@ -10,18 +10,33 @@ pub(crate) struct NameValue {
 
 pub(crate) type TmuxOption = NameValue;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub(crate) struct TmuxSession {
     pub(crate) background: Option<bool>,         // tmux -d
     pub(crate) no_recent_tracking: Option<bool>, // Won't be included in stmux recent next/previous.
     pub(crate) windows: Vec<TmuxWindow>,
     #[serde(default)]
     pub(crate) options: Vec<TmuxOption>,
+    /// Inherited by every pane of the session.
+    #[serde(default)]
+    pub(crate) environment: Vec<EnvironmentVariable>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) template: Option<TemplateLink>,
+}
+
+/// The template a session was created from, so that later template changes can be applied again.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct TemplateLink {
+    pub(crate) name: String,
+    pub(crate) variables: Vec<TemplateVariable>,
 }
 
+/// Replaces `${name}` placeholders in templates.
+pub(crate) type TemplateVariable = NameValue;
+
 pub(crate) type SessionName = String;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub(crate) struct TmuxWindow {
     pub(crate) index: usize,
     pub(crate) name: WindowName,
@@ -29,11 +44,14 @@ pub(crate) struct TmuxWindow {
     pub(crate) panes: Vec<TmuxPane>,
     pub(crate) options: Vec<TmuxOption>,
     pub(crate) active: Option<bool>,
+    /// Inherited by every pane of the window, overriding the session ones.
+    #[serde(default)]
+    pub(crate) environment: Vec<EnvironmentVariable>,
 }
 
 pub(crate) type WindowName = String;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub(crate) struct TmuxPane {
     pub(crate) index: usize,
     pub(crate) path: String,
@@ -75,6 +93,17 @@ pub(crate) struct StatusPane {
     pub(crate) active: bool,
 }
 
+/// A stored session as shown by `sessions list`.
+#[derive(Serialize, Debug)]
+pub(crate) struct SessionSummary {
+    pub(crate) name: SessionName,
+    pub(crate) windows: usize,
+    pub(crate) panes: usize,
+    pub(crate) live: bool,
+    pub(crate) bookmarked: bool,
+    pub(crate) recent: bool,
+}
+
 #[derive(Debug)]
 pub(crate) struct StatusWindow {
     pub(crate) name: WindowName,
@@ -82,14 +111,3 @@ pub(crate) struct StatusWindow {
     pub(crate) active: bool,
     pub(crate) panes: Vec<StatusPane>,
 }
-
-impl TmuxWindow {
-    /// `index` is 1-based (tmux style)
-    pub(crate) fn startup_command_for_pane(&self, index: usize) -> Option<String> {
-        if self.panes.is_empty() {
-            None
-        } else {
-            self.panes[index - 1].startup_command.clone()
-        }
-    }
-}
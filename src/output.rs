@@ -0,0 +1,25 @@
+use serde::Serialize;
+
+use crate::{
+    args::OutputFormat,
+    error::{Error, Result},
+};
+
+/// Prints the result of a list command. Without `--format` the plain lines go to stderr, as they
+/// always have. With `--format plain` they go to stdout, and `--format json` prints the value.
+pub(crate) fn print<T: Serialize>(
+    format: Option<&OutputFormat>,
+    value: &T,
+    plain_lines: &[String],
+) -> Result<()> {
+    match format {
+        None => plain_lines.iter().for_each(|line| eprintln!("{}", line)),
+        Some(OutputFormat::Plain) => plain_lines.iter().for_each(|line| println!("{}", line)),
+        Some(OutputFormat::Json) => println!(
+            "{}",
+            serde_json::to_string_pretty(value).map_err(|error| Error::parse("output", error))?
+        ),
+    }
+
+    Ok(())
+}
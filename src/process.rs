@@ -0,0 +1,138 @@
+use std::fs;
+
+use crate::settings::SaveSettings;
+use crate::utils::shell_quote;
+
+/// Programs that are considered idle panes rather than something worth restoring.
+const SHELLS: [&str; 9] = [
+    "bash", "zsh", "fish", "sh", "dash", "ksh", "tcsh", "csh", "nu",
+];
+
+/// Returns the command line of the foreground process running in a pane, if it is something
+/// other than the pane's shell and the settings allow recording it.
+///
+/// `pane_pid` is `#{pane_pid}` and `current_command` is `#{pane_current_command}`, which is used
+/// when `/proc` is not available.
+pub(crate) fn restorable_command(
+    pane_pid: u32,
+    current_command: &str,
+    settings: &SaveSettings,
+) -> Option<String> {
+    let args = foreground_pid(pane_pid)
+        .and_then(read_cmdline)
+        .unwrap_or_else(|| vec![current_command.to_string()]);
+
+    to_command(&args, settings)
+}
+
+/// Same as `restorable_command`, for a command line recorded elsewhere, e.g. by tmux-resurrect.
+pub(crate) fn restorable_command_line(
+    command_line: &str,
+    settings: &SaveSettings,
+) -> Option<String> {
+    let program = program_name(command_line.split_whitespace().next()?);
+
+    is_restorable(program, settings).then(|| command_line.trim().to_string())
+}
+
+fn foreground_pid(pane_pid: u32) -> Option<u32> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pane_pid)).ok()?;
+
+    match parse_tpgid(&stat) {
+        Some(tpgid) if tpgid > 0 => Some(tpgid as u32),
+        _ => Some(pane_pid),
+    }
+}
+
+/// Extracts the terminal's foreground process group from `/proc/<pid>/stat`.
+fn parse_tpgid(stat: &str) -> Option<i32> {
+    // The executable name is in parentheses and may contain spaces, so skip past it.
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(5)?.parse().ok()
+}
+
+fn read_cmdline(pid: u32) -> Option<Vec<String>> {
+    let content = fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    let args: Vec<String> = content
+        .split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).to_string())
+        .collect();
+
+    if args.is_empty() { None } else { Some(args) }
+}
+
+fn program_name(arg: &str) -> &str {
+    let name = arg.rsplit('/').next().unwrap_or(arg);
+    name.trim_start_matches('-')
+}
+
+fn is_restorable(program: &str, settings: &SaveSettings) -> bool {
+    if program.is_empty() || SHELLS.contains(&program) {
+        return false;
+    }
+
+    if !settings.allowed_commands.is_empty()
+        && !settings.allowed_commands.iter().any(|c| c == program)
+    {
+        return false;
+    }
+
+    !settings.denied_commands.iter().any(|c| c == program)
+}
+
+fn to_command(args: &[String], settings: &SaveSettings) -> Option<String> {
+    if !is_restorable(program_name(args.first()?), settings) {
+        return None;
+    }
+
+    Some(
+        args.iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<String>>()
+            .join(" "),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn should_parse_tpgid_with_spaces_in_executable_name() {
+        let stat = "1234 (tmux: server) S 1 1234 1234 34816 5678 4194560 0 0";
+        assert_eq!(parse_tpgid(stat), Some(5678));
+    }
+
+    #[test]
+    fn should_ignore_shells() {
+        let settings = SaveSettings::default();
+        assert_eq!(to_command(&args(&["-zsh"]), &settings), None);
+        assert_eq!(to_command(&args(&["/bin/bash"]), &settings), None);
+    }
+
+    #[test]
+    fn should_quote_arguments() {
+        let settings = SaveSettings::default();
+        let result = to_command(&args(&["/usr/bin/nvim", "my file.txt"]), &settings);
+        assert_eq!(result.as_deref(), Some("/usr/bin/nvim 'my file.txt'"));
+    }
+
+    #[test]
+    fn should_respect_allowed_and_denied_commands() {
+        let settings = SaveSettings {
+            allowed_commands: vec!["nvim".into(), "htop".into()],
+            denied_commands: vec!["htop".into()],
+        };
+        assert_eq!(
+            to_command(&args(&["nvim"]), &settings).as_deref(),
+            Some("nvim")
+        );
+        assert_eq!(to_command(&args(&["htop"]), &settings), None);
+        assert_eq!(to_command(&args(&["npm", "run", "dev"]), &settings), None);
+    }
+}
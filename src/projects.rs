@@ -0,0 +1,242 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use dirs_next::home_dir;
+
+use crate::{
+    model::{SessionName, TmuxPane, TmuxSession, TmuxWindow},
+    settings::ProjectsSettings,
+    utils,
+};
+
+/// Marks picker entries which create a session for a project instead of selecting one.
+pub(crate) const PROJECT_ENTRY_PREFIX: &str = "+ ";
+
+/// A git repository below one of the project roots.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Project {
+    pub(crate) name: SessionName,
+    pub(crate) path: String,
+}
+
+pub(crate) trait Projects {
+    /// Git repositories below the configured roots, sorted by path. Unreadable directories are
+    /// skipped.
+    fn discover(&self) -> Vec<Project>;
+}
+
+pub(crate) struct ProjectsImpl<'s> {
+    settings: &'s ProjectsSettings,
+}
+
+impl<'s> ProjectsImpl<'s> {
+    pub(crate) fn new(settings: &'s ProjectsSettings) -> Self {
+        Self { settings }
+    }
+
+    fn scan(&self, directory: &Path, depth: usize, projects: &mut Vec<Project>) {
+        if directory.join(".git").exists() {
+            let path = directory.to_string_lossy().to_string();
+            projects.push(Project {
+                name: session_name(&path),
+                path,
+            });
+            return;
+        }
+
+        if depth == self.settings.max_depth {
+            return;
+        }
+
+        let Ok(entries) = fs::read_dir(directory) else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+
+            if !hidden && path.is_dir() {
+                self.scan(&path, depth + 1, projects);
+            }
+        }
+    }
+}
+
+impl Projects for ProjectsImpl<'_> {
+    fn discover(&self) -> Vec<Project> {
+        let mut projects = vec![];
+
+        for root in &self.settings.roots {
+            self.scan(&PathBuf::from(expand_home(root)), 0, &mut projects);
+        }
+
+        projects.sort_by(|a, b| a.path.cmp(&b.path));
+        projects.dedup();
+        projects
+    }
+}
+
+/// The picker entry of a project, e.g. `+ ~/src/stmux`.
+pub(crate) fn picker_entry(project: &Project) -> String {
+    let path = match home_dir().and_then(|home| {
+        Path::new(&project.path)
+            .strip_prefix(home)
+            .ok()
+            .map(|p| p.to_path_buf())
+    }) {
+        Some(relative) => format!("~/{}", relative.to_string_lossy()),
+        None => project.path.clone(),
+    };
+
+    format!("{}{}", PROJECT_ENTRY_PREFIX, path)
+}
+
+/// Replaces a leading `~` with the home directory.
+pub(crate) fn expand_home(path: &str) -> String {
+    match (path.strip_prefix('~'), home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            format!("{}{}", home.to_string_lossy(), rest)
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// The directory name, with the characters tmux doesn't allow in session names replaced.
+pub(crate) fn session_name(path: &str) -> SessionName {
+    Path::new(path.trim_end_matches('/'))
+        .file_name()
+        .map(|name| utils::valid_session_name(&name.to_string_lossy()))
+        .unwrap_or_else(|| "project".to_string())
+}
+
+/// The session of the project: the one named after its directory, unless that name belongs to a
+/// session of another directory, then the parent directory name is prepended, and a number
+/// appended if that's taken too. `pane_paths` returns the pane paths of the session with the name,
+/// `None` if there's no such session.
+pub(crate) fn project_session_name(
+    path: &str,
+    pane_paths: impl Fn(&str) -> Option<Vec<String>>,
+) -> SessionName {
+    let path = path.trim_end_matches('/');
+    let name = session_name(path);
+    let qualified_name = match Path::new(path).parent().and_then(Path::file_name) {
+        Some(parent) => {
+            utils::valid_session_name(&format!("{}-{}", parent.to_string_lossy(), name))
+        }
+        None => name.clone(),
+    };
+    let numbered_names = (2..).map(|number| format!("{}-{}", qualified_name, number));
+
+    [name, qualified_name.clone()]
+        .into_iter()
+        .chain(numbered_names)
+        .find(|candidate| match pane_paths(candidate) {
+            Some(pane_paths) => pane_paths
+                .iter()
+                .any(|pane_path| pane_path == path || pane_path.starts_with(&format!("{}/", path))),
+            None => true,
+        })
+        .unwrap_or(qualified_name)
+}
+
+/// A single window session rooted at the project, used when no template is configured.
+pub(crate) fn default_session(project: &Project) -> TmuxSession {
+    TmuxSession {
+        background: None,
+        no_recent_tracking: None,
+        windows: vec![TmuxWindow {
+            index: 1,
+            name: project.name.clone(),
+            layout: String::new(),
+            panes: vec![TmuxPane {
+                index: 1,
+                path: project.path.clone(),
+                active: true,
+                startup_command: None,
+                shell_command: None,
+                environment: vec![],
+                name: None,
+            }],
+            options: vec![],
+            active: Some(true),
+            environment: vec![],
+        }],
+        options: vec![],
+        environment: vec![],
+        template: None,
+    }
+}
+
+#[cfg(test)]
+mod discover_tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+
+    #[test]
+    fn should_find_repositories_up_to_the_maximum_depth() {
+        // Given
+        let directory = TempDir::new();
+        let root = directory.path();
+        for directory in [
+            "api/.git",
+            "group/web/.git",
+            "group/deep/er/.git",
+            "notes",
+            ".hidden/.git",
+        ] {
+            fs::create_dir_all(root.join(directory)).unwrap();
+        }
+        let settings = ProjectsSettings {
+            roots: vec![root.to_string_lossy().to_string()],
+            max_depth: 2,
+            template: None,
+        };
+
+        // When
+        let result = ProjectsImpl::new(&settings).discover();
+
+        // Then
+        assert_eq!(
+            result,
+            vec![
+                Project {
+                    name: "api".to_string(),
+                    path: root.join("api").to_string_lossy().to_string(),
+                },
+                Project {
+                    name: "web".to_string(),
+                    path: root.join("group/web").to_string_lossy().to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_name_sessions_of_same_named_repositories_apart() {
+        // Given
+        let pane_paths = |name: &str| match name {
+            "api" => Some(vec!["/home/me/work/api/src".to_string()]),
+            "personal-api" => Some(vec!["/home/me/notes".to_string()]),
+            _ => None,
+        };
+
+        // When
+        let work = project_session_name("/home/me/work/api", pane_paths);
+        let personal = project_session_name("/home/me/personal/api", pane_paths);
+        let other = project_session_name("/home/me/other/api/", pane_paths);
+
+        // Then
+        assert_eq!(work, "api");
+        assert_eq!(personal, "personal-api-2");
+        assert_eq!(other, "other-api");
+    }
+
+    #[test]
+    fn should_replace_characters_tmux_does_not_allow_in_session_names() {
+        assert_eq!(session_name("/home/me/src/example.com"), "example_com");
+        assert_eq!(session_name("/home/me/src/api/"), "api");
+    }
+}
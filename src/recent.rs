@@ -1,20 +1,25 @@
 use std::cmp::max;
 
 use crate::{
+    args::OutputFormat,
     config::Config,
+    error::Result,
     model::{TmuxSession, WindowDimension},
+    output,
     session::{Session, SessionImpl},
     session_name_file::SessionNameFile,
+    settings::Settings,
     tmux::Tmux,
     utils,
 };
 
 pub(crate) trait Recent {
-    fn add(&self, session: Option<&TmuxSession>, session_name: &str);
-    fn next(&self, session_name: &str) -> Option<String>;
-    fn previous(&self, session_name: &str) -> Option<String>;
-    fn print(&self);
-    fn edit(&self, config: &dyn Config);
+    fn add(&self, session: Option<&TmuxSession>, session_name: &str) -> Result<()>;
+    fn next(&self, session_name: &str) -> Result<Option<String>>;
+    fn previous(&self, session_name: &str) -> Result<Option<String>>;
+    fn remove(&self, session_name: &str) -> Result<()>;
+    fn print(&self, format: Option<&OutputFormat>) -> Result<()>;
+    fn edit(&self, config: &dyn Config, settings: &Settings) -> Result<()>;
 }
 
 pub(crate) struct RecentImpl<'t, 's, T: Tmux, S: SessionNameFile> {
@@ -32,25 +37,23 @@ impl<'t, 's, T: Tmux, S: SessionNameFile> RecentImpl<'t, 's, T, S> {
 }
 
 impl<'t, 's, T: Tmux, S: SessionNameFile> Recent for RecentImpl<'t, 's, T, S> {
-    fn add(&self, session: Option<&TmuxSession>, session_name: &str) {
+    fn add(&self, session: Option<&TmuxSession>, session_name: &str) -> Result<()> {
         if utils::is_numeric(session_name) {
-            return;
+            return Ok(());
         }
 
         if let Some(session) = session
             && let Some(no_recent_tracking) = session.no_recent_tracking
             && no_recent_tracking
         {
-            return;
+            return Ok(());
         }
 
-        let mut names: Vec<String> = vec![session_name.to_string()];
-        self.recent_session_file.read_into(&mut names, session_name);
-        self.recent_session_file.write(&names);
+        self.recent_session_file.prepend(session_name)
     }
 
-    fn next(&self, session_name: &str) -> Option<String> {
-        let recent_session_names = self.recent_session_file.read();
+    fn next(&self, session_name: &str) -> Result<Option<String>> {
+        let recent_session_names = self.recent_session_file.read()?;
         let session = SessionImpl::new(self.tmux);
         let current_session_names = session.list_names();
         let session_names = recent_session_names
@@ -59,22 +62,22 @@ impl<'t, 's, T: Tmux, S: SessionNameFile> Recent for RecentImpl<'t, 's, T, S> {
             .collect::<Vec<String>>();
 
         if session_names.is_empty() {
-            return None;
+            return Ok(None);
         }
 
         if !session_names.contains(&session_name.to_string()) {
-            return Some(session_names[0].clone());
+            return Ok(Some(session_names[0].clone()));
         }
 
-        session_names
+        Ok(session_names
             .iter()
             .skip_while(|&name| name != session_name)
             .nth(1)
-            .cloned()
+            .cloned())
     }
 
-    fn previous(&self, session_name: &str) -> Option<String> {
-        let recent_session_names = self.recent_session_file.read();
+    fn previous(&self, session_name: &str) -> Result<Option<String>> {
+        let recent_session_names = self.recent_session_file.read()?;
         let session = SessionImpl::new(self.tmux);
         let current_session_names = session.list_names();
         let session_names = recent_session_names
@@ -83,34 +86,41 @@ impl<'t, 's, T: Tmux, S: SessionNameFile> Recent for RecentImpl<'t, 's, T, S> {
             .collect::<Vec<String>>();
 
         if session_names.is_empty() {
-            return None;
+            return Ok(None);
         }
 
         let mut previous_name = None;
 
         for name in session_names.iter() {
             if name == session_name {
-                return previous_name.clone();
+                return Ok(previous_name);
             }
 
             previous_name = Some(name.clone());
         }
 
-        None
+        Ok(None)
     }
 
-    fn print(&self) {
-        let recent_session_names = &self.recent_session_file.read();
+    fn remove(&self, session_name: &str) -> Result<()> {
+        self.recent_session_file.remove(session_name)
+    }
 
-        for name in recent_session_names {
-            eprintln!("{}", name.trim());
-        }
+    fn print(&self, format: Option<&OutputFormat>) -> Result<()> {
+        let recent_session_names = self
+            .recent_session_file
+            .read()?
+            .iter()
+            .map(|name| name.trim().to_string())
+            .collect::<Vec<_>>();
+
+        output::print(format, &recent_session_names, &recent_session_names)
     }
 
-    fn edit(&self, config: &dyn Config) {
+    fn edit(&self, config: &dyn Config, settings: &Settings) -> Result<()> {
         let width = self
             .recent_session_file
-            .read()
+            .read()?
             .iter()
             .map(|s| s.len())
             .max()
@@ -118,23 +128,22 @@ impl<'t, 's, T: Tmux, S: SessionNameFile> Recent for RecentImpl<'t, 's, T, S> {
 
         let popup_width = max(width + 6, 21);
         let popup_height = 7;
-        let y = self.tmux.window_dimension().map(|d| d.height / 2 - 1);
+        let y = self.tmux.window_dimension()?.map(|d| d.height / 2 - 1);
 
         self.tmux.display_popup(
             "Recent sessions",
-            &Some("fg=#9f7fff italics align=centre".to_string()),
-            "fg=#806aba", // TODO: put in the config
+            &Some(settings.popup.title_style.clone()),
+            &settings.popup.style,
             &WindowDimension {
                 width: popup_width,
                 height: popup_height,
             },
             &y,
-            &format!(
-                "nvim --clean -u {} {}",
-                config.neovim_config_filename(),
-                config.recent_sessions_filename()
+            &settings.editor_command(
+                &config.neovim_config_filename(),
+                &config.recent_sessions_filename(),
             ),
-        );
+        )
     }
 }
 
@@ -155,12 +164,12 @@ mod next_tests {
         let mut recent_session_file = MockSessionNameFile::new();
         recent_session_file
             .expect_read()
-            .returning(|| vec!["a".into(), "b".into()]);
+            .returning(|| Ok(vec!["a".into(), "b".into()]));
 
         let recent = RecentImpl::new(&tmux, &recent_session_file);
 
         // When
-        let result = recent.next("a");
+        let result = recent.next("a").unwrap();
 
         // Then
         assert_eq!(result.as_deref(), Some("b"));
@@ -176,12 +185,12 @@ mod next_tests {
         let mut recent_session_file = MockSessionNameFile::new();
         recent_session_file
             .expect_read()
-            .returning(|| vec!["a".into(), "b".into(), "c".into()]);
+            .returning(|| Ok(vec!["a".into(), "b".into(), "c".into()]));
 
         let recent = RecentImpl::new(&tmux, &recent_session_file);
 
         // When
-        let result = recent.next("a");
+        let result = recent.next("a").unwrap();
 
         // Then
         assert_eq!(result.as_deref(), Some("c"));
@@ -197,12 +206,12 @@ mod next_tests {
         let mut recent_session_file = MockSessionNameFile::new();
         recent_session_file
             .expect_read()
-            .returning(|| vec!["a".into(), "b".into(), "c".into()]);
+            .returning(|| Ok(vec!["a".into(), "b".into(), "c".into()]));
 
         let recent = RecentImpl::new(&tmux, &recent_session_file);
 
         // When
-        let result = recent.next("c");
+        let result = recent.next("c").unwrap();
 
         // Then
         assert_eq!(result.as_deref(), None);
@@ -226,12 +235,12 @@ mod previous_tests {
         let mut recent_session_file = MockSessionNameFile::new();
         recent_session_file
             .expect_read()
-            .returning(|| vec!["a".into(), "b".into(), "c".into()]);
+            .returning(|| Ok(vec!["a".into(), "b".into(), "c".into()]));
 
         let recent = RecentImpl::new(&tmux, &recent_session_file);
 
         // When
-        let result = recent.previous("c");
+        let result = recent.previous("c").unwrap();
 
         // Then
         assert_eq!(result.as_deref(), Some("b"));
@@ -247,12 +256,12 @@ mod previous_tests {
         let mut recent_session_file = MockSessionNameFile::new();
         recent_session_file
             .expect_read()
-            .returning(|| vec!["a".into(), "b".into(), "c".into()]);
+            .returning(|| Ok(vec!["a".into(), "b".into(), "c".into()]));
 
         let recent = RecentImpl::new(&tmux, &recent_session_file);
 
         // When
-        let result = recent.previous("c");
+        let result = recent.previous("c").unwrap();
 
         // Then
         assert_eq!(result.as_deref(), Some("a"));
@@ -268,12 +277,12 @@ mod previous_tests {
         let mut recent_session_file = MockSessionNameFile::new();
         recent_session_file
             .expect_read()
-            .returning(|| vec!["a".into(), "b".into(), "c".into()]);
+            .returning(|| Ok(vec!["a".into(), "b".into(), "c".into()]));
 
         let recent = RecentImpl::new(&tmux, &recent_session_file);
 
         // When
-        let result = recent.previous("a");
+        let result = recent.previous("a").unwrap();
 
         // Then
         assert_eq!(result.as_deref(), None);
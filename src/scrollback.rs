@@ -0,0 +1,183 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::state_file;
+use crate::utils::shell_quote;
+
+const SCROLLBACK_DIRNAME: &str = "scrollback";
+
+pub(crate) struct PaneContent {
+    pub(crate) window_index: usize,
+    pub(crate) pane_index: usize,
+    pub(crate) content: String,
+}
+
+pub(crate) trait Scrollback {
+    /// Replaces all stored pane contents of a session, keeping at most `max_bytes` in total.
+    fn save(&self, session_name: &str, panes: Vec<PaneContent>, max_bytes: usize) -> Result<()>;
+    /// Moves the stored pane contents of a session to its new name.
+    fn rename(&self, session_name: &str, new_name: &str) -> Result<()>;
+    /// A shell command that prints the stored pane content and then starts the user's shell. The
+    /// content is kept until the next save replaces it, so an interrupted restore doesn't lose it.
+    fn replay_command(
+        &self,
+        session_name: &str,
+        window_index: usize,
+        pane_index: usize,
+    ) -> Option<String>;
+}
+
+pub(crate) struct ScrollbackImpl {
+    dirname: PathBuf,
+}
+
+impl ScrollbackImpl {
+    /// Pane contents are kept in a directory next to the sessions file.
+    pub(crate) fn new(sessions_filename: &str) -> Self {
+        let dirname = Path::new(sessions_filename)
+            .parent()
+            .map(|p| p.join(SCROLLBACK_DIRNAME))
+            .unwrap_or(PathBuf::from(SCROLLBACK_DIRNAME));
+
+        Self { dirname }
+    }
+
+    fn session_dirname(&self, session_name: &str) -> PathBuf {
+        self.dirname.join(session_name.replace('/', "_"))
+    }
+
+    fn filename(&self, session_name: &str, window_index: usize, pane_index: usize) -> PathBuf {
+        self.session_dirname(session_name)
+            .join(format!("{}.{}", window_index, pane_index))
+    }
+}
+
+impl Scrollback for ScrollbackImpl {
+    fn save(&self, session_name: &str, panes: Vec<PaneContent>, max_bytes: usize) -> Result<()> {
+        let dirname = self.session_dirname(session_name);
+        let _ = fs::remove_dir_all(&dirname);
+
+        if panes.is_empty() {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&dirname).map_err(Error::io(dirname.display()))?;
+        let max_pane_bytes = max_bytes / panes.len();
+
+        for pane in panes {
+            let content = truncate_front(&pane.content, max_pane_bytes);
+
+            if content.is_empty() {
+                continue;
+            }
+
+            let filename = self.filename(session_name, pane.window_index, pane.pane_index);
+            state_file::write(&filename.to_string_lossy(), &format!("{}\n", content))?;
+        }
+
+        Ok(())
+    }
+
+    fn rename(&self, session_name: &str, new_name: &str) -> Result<()> {
+        let dirname = self.session_dirname(session_name);
+
+        if !dirname.is_dir() {
+            return Ok(());
+        }
+
+        let new_dirname = self.session_dirname(new_name);
+        let _ = fs::remove_dir_all(&new_dirname);
+        fs::rename(&dirname, &new_dirname).map_err(Error::io(dirname.display()))
+    }
+
+    fn replay_command(
+        &self,
+        session_name: &str,
+        window_index: usize,
+        pane_index: usize,
+    ) -> Option<String> {
+        let filename = self.filename(session_name, window_index, pane_index);
+
+        if !filename.is_file() {
+            return None;
+        }
+
+        // Wrapped in `sh`, because tmux runs commands with the user's default shell.
+        let script = format!(
+            "cat {}; exec \"${{SHELL:-sh}}\"",
+            shell_quote(&filename.to_string_lossy())
+        );
+        Some(format!("sh -c {}", shell_quote(&script)))
+    }
+}
+
+/// Keeps the last lines of `content` that fit into `max_bytes`.
+fn truncate_front(content: &str, max_bytes: usize) -> &str {
+    let content = content.trim_end();
+
+    if content.len() <= max_bytes {
+        return content;
+    }
+
+    // Searching from the byte before `start` keeps a line that begins exactly at `start`. Slicing
+    // right after a newline is always a valid character boundary.
+    let start = content.len() - max_bytes - 1;
+
+    content.as_bytes()[start..]
+        .iter()
+        .position(|b| *b == b'\n')
+        .map(|i| &content[start + i + 1..])
+        .unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+    use crate::test_utils::TempDir;
+
+    #[test]
+    fn should_keep_content_that_fits() {
+        assert_eq!(truncate_front("a\nb\n\n", 10), "a\nb");
+    }
+
+    #[test]
+    fn should_drop_whole_lines_from_the_front() {
+        assert_eq!(truncate_front("first\nsecond\nthird", 10), "third");
+        assert_eq!(truncate_front("first\nsecond\nthird", 12), "second\nthird");
+    }
+
+    #[test]
+    fn should_not_split_multibyte_characters() {
+        assert_eq!(truncate_front("żółw\nab", 4), "ab");
+    }
+
+    #[test]
+    fn should_keep_the_content_until_the_next_save() {
+        // Given
+        let directory = TempDir::new();
+        let scrollback = ScrollbackImpl::new(&directory.filename("sessions.toml"));
+        let pane = PaneContent {
+            window_index: 1,
+            pane_index: 2,
+            content: "output".to_string(),
+        };
+        scrollback.save("work", vec![pane], 1024).unwrap();
+
+        // When
+        let result = scrollback.replay_command("work", 1, 2).unwrap();
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(result.replace("exec ", "true "))
+            .output()
+            .unwrap();
+
+        // Then
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "output\n");
+        assert_eq!(scrollback.replay_command("work", 1, 2), Some(result));
+        scrollback.save("work", vec![], 1024).unwrap();
+        assert_eq!(scrollback.replay_command("work", 1, 2), None);
+    }
+}
@@ -8,22 +8,85 @@ use std::{
     thread,
 };
 
+use crate::error::{Error, Result};
 use crate::model::{SessionName, TmuxSession, TmuxSessions};
+use crate::process;
+use crate::projects::PROJECT_ENTRY_PREFIX;
+use crate::recent::Recent;
+use crate::scrollback::{PaneContent, Scrollback};
+use crate::session_name_file::SessionNameFile;
 use crate::sessions::SessionStorage;
+use crate::settings::{PickerSettings, SaveSettings};
 use crate::tmux::Tmux;
+use crate::utils::{self, PathRewrite};
 use crate::window::{Window, WindowImpl};
 
-const FZF_DEFAULT_OPTS: &str = "--bind=alt-q:close,alt-j:down,alt-k:up,alt-u:page-up,alt-d:page-down,tab:accept --color=fg:#cdd6f4,header:#f38ba8,info:#cba6f7,pointer:#f5e0dc --color=marker:#b4befe,fg+:#cdd6f4,prompt:#cba6f7,hl+:#f38ba8 --color=selected-bg:#45475a";
-
 pub(crate) trait Session {
-    fn find(&self, session_names: Vec<SessionName>, title: Option<String>);
-    fn select(&self, name: &str, sessions: &dyn SessionStorage);
-    fn save(&self, sessions: &dyn SessionStorage);
-    fn delete(&self, session_name: &str, sessions: &dyn SessionStorage);
-    fn update(&self, session_name: &str, session: TmuxSession, sessions: &dyn SessionStorage);
-    fn list(&self) -> TmuxSessions;
+    fn find(
+        &self,
+        session_names: Vec<SessionName>,
+        title: Option<String>,
+        picker: &PickerSettings,
+    ) -> Result<()>;
+    fn select(&self, name: &str, sessions: &dyn SessionStorage) -> Result<()>;
+    fn save(
+        &self,
+        session_name: &str,
+        sessions: &dyn SessionStorage,
+        settings: &SaveSettings,
+    ) -> Result<()>;
+    fn save_scrollback(
+        &self,
+        session_name: &str,
+        scrollback: &dyn Scrollback,
+        max_bytes: usize,
+    ) -> Result<()>;
+    fn delete(&self, session_name: &str, sessions: &dyn SessionStorage) -> Result<()>;
+    /// Kills the live session, saving it first if settings are given, and drops it from the recent
+    /// sessions. A client showing it is switched to the next session first, instead of being
+    /// detached. `forget` deletes the stored session too, also when it isn't running.
+    fn kill(
+        &self,
+        session_name: &str,
+        save: Option<&SaveSettings>,
+        forget: bool,
+        sessions: &dyn SessionStorage,
+        recent: &dyn Recent,
+    ) -> Result<()>;
+    /// Renames the live and the stored session, its stored pane contents, and the name in the
+    /// given name lists. Returns the new name, with the characters tmux doesn't allow replaced.
+    fn rename(
+        &self,
+        session_name: &str,
+        new_name: &str,
+        sessions: &dyn SessionStorage,
+        scrollback: &dyn Scrollback,
+        session_name_files: &[&dyn SessionNameFile],
+    ) -> Result<SessionName>;
+    /// Stores a copy of the stored session, or of the live one if it was never saved, under
+    /// another name, with the pane paths rewritten. Returns the new name, with the characters tmux
+    /// doesn't allow replaced.
+    fn clone(
+        &self,
+        session_name: &str,
+        new_name: &str,
+        path_rewrites: &[PathRewrite],
+        sessions: &dyn SessionStorage,
+        settings: &SaveSettings,
+    ) -> Result<SessionName>;
+    fn update(
+        &self,
+        session_name: &str,
+        session: TmuxSession,
+        sessions: &dyn SessionStorage,
+    ) -> Result<()>;
+    fn list(&self, settings: &SaveSettings) -> Result<TmuxSessions>;
+    /// Names of the live sessions. Empty if the tmux server is not running.
     fn list_names(&self) -> Vec<SessionName>;
-    fn list_other_session_names(&self) -> Vec<SessionName>;
+    fn list_other_session_names(&self) -> Result<Vec<SessionName>>;
+    /// Pane paths of the stored session, or else of the live one, `None` if there's neither.
+    fn pane_paths(&self, session_name: &str, stored_sessions: &TmuxSessions)
+    -> Option<Vec<String>>;
 }
 
 pub(crate) struct SessionImpl<'t, T: Tmux> {
@@ -34,11 +97,57 @@ impl<'t, T: Tmux> SessionImpl<'t, T> {
     pub(crate) fn new(tmux: &'t T) -> Self {
         Self { tmux }
     }
+
+    /// Builds a `TmuxSession` from the live state of the given session.
+    fn capture(&self, session_name: &str, settings: &SaveSettings) -> Result<TmuxSession> {
+        let window = WindowImpl::new(self.tmux);
+        let mut windows = window.list_with_pane_details(session_name)?;
+        let processes = self.tmux.list_session_panes(
+            session_name,
+            "#{window_index}\t#{pane_index}\t#{pane_pid}\t#{pane_current_command}",
+        )?;
+
+        for line in processes {
+            let tokens = line.splitn(4, '\t').collect::<Vec<&str>>();
+
+            if tokens.len() < 4 {
+                continue;
+            }
+
+            let window_index = tokens[0].parse::<usize>().unwrap_or(0);
+            let pane_index = tokens[1].parse::<usize>().unwrap_or(0);
+            let Ok(pane_pid) = tokens[2].parse::<u32>() else {
+                continue;
+            };
+
+            if let Some(pane) = windows
+                .iter_mut()
+                .find(|w| w.index == window_index)
+                .and_then(|w| w.panes.iter_mut().find(|p| p.index == pane_index))
+            {
+                pane.startup_command = process::restorable_command(pane_pid, tokens[3], settings);
+            }
+        }
+
+        Ok(TmuxSession {
+            background: None,
+            no_recent_tracking: None,
+            windows,
+            options: self.tmux.show_session_options(session_name)?,
+            environment: vec![],
+            template: None,
+        })
+    }
 }
 
 impl<'t, T: Tmux> Session for SessionImpl<'t, T> {
-    fn find(&self, session_names: Vec<SessionName>, title: Option<String>) {
-        let window_dimension = self.tmux.window_dimension();
+    fn find(
+        &self,
+        session_names: Vec<SessionName>,
+        title: Option<String>,
+        picker: &PickerSettings,
+    ) -> Result<()> {
+        let window_dimension = self.tmux.window_dimension()?;
         let input_fifo_path = "/tmp/stmux_fzf_input.fifo";
         let popup_title = title.map(|t| format!(" {} ", t));
         let title_len = popup_title.as_ref().map_or(0, |t| t.len() + 4);
@@ -48,7 +157,7 @@ impl<'t, T: Tmux> Session for SessionImpl<'t, T> {
             .max()
             .unwrap_or(title_len)
             + 6;
-        let max_height = 5;
+        let max_height = picker.max_height;
         let mut popup_width = max(width, title_len);
 
         if session_names.len() > max_height && width >= title_len {
@@ -61,7 +170,7 @@ impl<'t, T: Tmux> Session for SessionImpl<'t, T> {
             let _ = nix::unistd::mkfifo(input_fifo_path, nix::sys::stat::Mode::S_IRWXU);
         }
 
-        let write_thread = thread::spawn(move || -> Result<(), std::io::Error> {
+        let write_thread = thread::spawn(move || -> std::io::Result<()> {
             let mut fifo = File::create(input_fifo_path).unwrap();
 
             for item in session_names.into_iter() {
@@ -72,21 +181,15 @@ impl<'t, T: Tmux> Session for SessionImpl<'t, T> {
         });
 
         // TODO: Extract into fzf-popup module.
-        let cursor_color = "#a08afa";
-        let colors_table = [
-            "--color=border:#806aba",
-            "--color=scrollbar:#5c5068",
-            "--color=separator:#5c5068",
-            "--color=label:italic:#9f7fff",
-            "--color=gutter:#1a1323",
-            "--color=current-bg:#3a2943",
-            "--color=marker:#FF0000",
-        ];
-
-        let colors = colors_table.join(" ");
+        let colors = picker
+            .colors
+            .iter()
+            .map(|color| format!("--color={}", color))
+            .collect::<Vec<_>>()
+            .join(" ");
 
         // Get cursor position and position popup at cursor
-        let (x_pos, y_pos) = if let Some(cursor_pos) = self.tmux.get_cursor_position() {
+        let (x_pos, y_pos) = if let Some(cursor_pos) = self.tmux.get_cursor_position()? {
             (
                 format!("-x {} ", cursor_pos.x - 4),
                 format!("-y {} ", cursor_pos.y + height as i32 - 1),
@@ -127,7 +230,9 @@ impl<'t, T: Tmux> Session for SessionImpl<'t, T> {
             r#"echo -ne "\\e]12;{}\\a"; cat {} | fzf {} | {{
                 read -r key; read -r selection
 
-                if [[ "$key" == "{}" || "$key" == "{}" ]]; then
+                if [[ "$selection" == "{}"* ]]; then
+                    stmux session open-project "${{selection#"{}"}}"
+                elif [[ "$key" == "{}" || "$key" == "{}" ]]; then
                     stmux window smart-split left "$selection"
                 elif [[ "$key" == "{}" || "$key" == "{}" ]]; then
                     stmux window smart-split right "$selection"
@@ -135,9 +240,11 @@ impl<'t, T: Tmux> Session for SessionImpl<'t, T> {
                     stmux session select "$selection"
                 fi
             }}"#,
-            cursor_color,
+            picker.cursor_color,
             input_fifo_path,
             fzf_opts,
+            PROJECT_ENTRY_PREFIX,
+            PROJECT_ENTRY_PREFIX,
             split_left_key,
             split_left_alt_key,
             split_right_key,
@@ -148,7 +255,7 @@ impl<'t, T: Tmux> Session for SessionImpl<'t, T> {
             "tmux display-popup -E -B {}{}-e 'FZF_DEFAULT_OPTS={}' -w {} -h {} '{}'",
             x_pos,
             y_pos,
-            std::env::var("FZF_DEFAULT_OPTS").unwrap_or(FZF_DEFAULT_OPTS.to_string()),
+            std::env::var("FZF_DEFAULT_OPTS").unwrap_or(picker.fzf_options.clone()),
             popup_width,
             height,
             fzf_command
@@ -158,54 +265,246 @@ impl<'t, T: Tmux> Session for SessionImpl<'t, T> {
 
         let _ = write_thread.join().expect("Failed to join write thread");
         let _ = remove_file(input_fifo_path);
+
+        Ok(())
     }
 
-    fn select(&self, name: &str, sessions: &dyn SessionStorage) {
-        if !self.tmux.has_session(name) {
-            if let Some(background) = sessions.restore(name)
+    fn select(&self, name: &str, sessions: &dyn SessionStorage) -> Result<()> {
+        if !self.tmux.has_session(name)? {
+            if let Some(background) = sessions.restore(name)?
                 && !background
             {
-                self.tmux.select_session(name);
+                self.tmux.select_session(name)?;
             }
         } else {
-            self.tmux.select_session(name);
+            self.tmux.select_session(name)?;
         }
+
+        Ok(())
     }
 
-    fn save(&self, _: &dyn SessionStorage) {
-        unimplemented!()
+    fn save(
+        &self,
+        session_name: &str,
+        sessions: &dyn SessionStorage,
+        settings: &SaveSettings,
+    ) -> Result<()> {
+        let session = self.capture(session_name, settings)?;
+
+        sessions.modify(&mut |stored_sessions| {
+            let stored = stored_sessions.remove(session_name);
+            let merged = utils::merge_session(stored, session.clone());
+            stored_sessions.insert(session_name.to_string(), merged);
+        })
     }
 
-    fn delete(&self, session_name: &str, sessions: &dyn SessionStorage) {
-        let mut stored_sessions = sessions.load();
-        stored_sessions.remove(session_name);
-        sessions.save(stored_sessions);
+    fn save_scrollback(
+        &self,
+        session_name: &str,
+        scrollback: &dyn Scrollback,
+        max_bytes: usize,
+    ) -> Result<()> {
+        let mut panes = Vec::new();
+
+        for line in self
+            .tmux
+            .list_session_panes(session_name, "#{window_index}\t#{pane_index}")?
+        {
+            let Some((window_index, pane_index)) = line.split_once('\t') else {
+                continue;
+            };
+            let (Ok(window_index), Ok(pane_index)) =
+                (window_index.parse::<usize>(), pane_index.parse::<usize>())
+            else {
+                continue;
+            };
+
+            panes.push(PaneContent {
+                window_index,
+                pane_index,
+                content: self
+                    .tmux
+                    .capture_pane(session_name, window_index, pane_index)?,
+            });
+        }
+
+        scrollback.save(session_name, panes, max_bytes)
     }
 
-    fn update(&self, session_name: &str, session: TmuxSession, sessions: &dyn SessionStorage) {
-        let mut stored_sessions = sessions.load();
-        stored_sessions.insert(session_name.to_string(), session);
-        sessions.save(stored_sessions);
+    fn delete(&self, session_name: &str, sessions: &dyn SessionStorage) -> Result<()> {
+        sessions.modify(&mut |stored_sessions| {
+            stored_sessions.remove(session_name);
+        })
     }
 
-    fn list(&self) -> TmuxSessions {
+    fn kill(
+        &self,
+        session_name: &str,
+        save: Option<&SaveSettings>,
+        forget: bool,
+        sessions: &dyn SessionStorage,
+        recent: &dyn Recent,
+    ) -> Result<()> {
+        let live = self.tmux.has_session(session_name)?;
+
+        if !live && !sessions.load()?.contains_key(session_name) {
+            return Err(Error::NotFound(format!(
+                "Session '{}' not found.",
+                session_name
+            )));
+        }
+
+        let mut next_session_name = None;
+
+        if live {
+            if let Some(settings) = save {
+                self.save(session_name, sessions, settings)?;
+            }
+
+            next_session_name = match recent.next(session_name)? {
+                Some(name) => Some(name),
+                None => recent.previous(session_name)?,
+            }
+            .or_else(|| {
+                self.list_names()
+                    .into_iter()
+                    .find(|name| name != session_name)
+            });
+
+            if let Some(next_session_name) = &next_session_name
+                && self.tmux.current_session_name()? == session_name
+            {
+                self.tmux.select_session(next_session_name)?;
+            }
+
+            self.tmux.kill_session(session_name)?;
+        }
+
+        recent.remove(session_name)?;
+
+        if forget {
+            self.delete(session_name, sessions)?;
+        }
+
+        if next_session_name.is_some() {
+            self.tmux.refresh_status()?;
+        }
+
+        Ok(())
+    }
+
+    fn rename(
+        &self,
+        session_name: &str,
+        new_name: &str,
+        sessions: &dyn SessionStorage,
+        scrollback: &dyn Scrollback,
+        session_name_files: &[&dyn SessionNameFile],
+    ) -> Result<SessionName> {
+        let new_name = utils::valid_session_name(new_name);
+        let new_name = new_name.as_str();
+        let stored_sessions = sessions.load()?;
+        let live = self.tmux.has_session(session_name)?;
+
+        if !live && !stored_sessions.contains_key(session_name) {
+            return Err(Error::NotFound(format!(
+                "Session '{}' not found.",
+                session_name
+            )));
+        }
+
+        if self.tmux.has_session(new_name)? || stored_sessions.contains_key(new_name) {
+            return Err(Error::AlreadyExists(format!(
+                "Session '{}' already exists.",
+                new_name
+            )));
+        }
+
+        if live {
+            self.tmux.rename_session(session_name, new_name)?;
+        }
+
+        sessions.modify(&mut |stored_sessions| {
+            if let Some(mut session) = stored_sessions.remove(session_name) {
+                // Applying the template again would bring back the old name otherwise.
+                if let Some(link) = &mut session.template {
+                    for variable in link.variables.iter_mut().filter(|v| v.name == "name") {
+                        variable.value = new_name.to_string();
+                    }
+                }
+
+                stored_sessions.insert(new_name.to_string(), session);
+            }
+        })?;
+        scrollback.rename(session_name, new_name)?;
+
+        for session_name_file in session_name_files {
+            session_name_file.rename(session_name, new_name)?;
+        }
+
+        Ok(new_name.to_string())
+    }
+
+    fn clone(
+        &self,
+        session_name: &str,
+        new_name: &str,
+        path_rewrites: &[PathRewrite],
+        sessions: &dyn SessionStorage,
+        settings: &SaveSettings,
+    ) -> Result<SessionName> {
+        let new_name = utils::valid_session_name(new_name);
+        let new_name = new_name.as_str();
+        let stored_sessions = sessions.load()?;
+        let stored = stored_sessions.get(session_name);
+
+        if stored.is_none() && !self.tmux.has_session(session_name)? {
+            return Err(Error::NotFound(format!(
+                "Session '{}' not found.",
+                session_name
+            )));
+        }
+
+        if stored_sessions.contains_key(new_name) || self.tmux.has_session(new_name)? {
+            return Err(Error::AlreadyExists(format!(
+                "Session '{}' already exists.",
+                new_name
+            )));
+        }
+
+        let mut copy = match stored {
+            Some(stored) => stored.clone(),
+            None => self.capture(session_name, settings)?,
+        };
+        utils::rewrite_paths(&mut copy, path_rewrites);
+        // The template variables still name the original, applying them would undo the copy.
+        copy.template = None;
+        self.update(new_name, copy, sessions)?;
+
+        Ok(new_name.to_string())
+    }
+
+    fn update(
+        &self,
+        session_name: &str,
+        session: TmuxSession,
+        sessions: &dyn SessionStorage,
+    ) -> Result<()> {
+        sessions.modify(&mut |stored_sessions| {
+            stored_sessions.insert(session_name.to_string(), session.clone());
+        })
+    }
+
+    fn list(&self, settings: &SaveSettings) -> Result<TmuxSessions> {
         let session_names = self.list_names();
         let mut sessions = HashMap::new();
-        let window = WindowImpl::new(self.tmux);
 
         for name in session_names {
-            let windows = window.list_with_pane_details(name.as_str());
-            let session = TmuxSession {
-                background: None,
-                no_recent_tracking: None,
-                windows,
-                options: vec![],
-            };
-
+            let session = self.capture(&name, settings)?;
             sessions.insert(name.to_string(), session);
         }
 
-        sessions
+        Ok(sessions)
     }
 
     fn list_names(&self) -> Vec<SessionName> {
@@ -214,11 +513,397 @@ impl<'t, T: Tmux> Session for SessionImpl<'t, T> {
             .unwrap_or_default()
     }
 
-    fn list_other_session_names(&self) -> Vec<SessionName> {
-        let current_session_name = self.tmux.current_session_name();
-        self.list_names()
+    fn list_other_session_names(&self) -> Result<Vec<SessionName>> {
+        let current_session_name = self.tmux.current_session_name()?;
+
+        Ok(self
+            .list_names()
             .into_iter()
             .filter(|s| s != &current_session_name)
-            .collect()
+            .collect())
+    }
+
+    fn pane_paths(
+        &self,
+        session_name: &str,
+        stored_sessions: &TmuxSessions,
+    ) -> Option<Vec<String>> {
+        if let Some(stored) = stored_sessions.get(session_name) {
+            return Some(
+                stored
+                    .windows
+                    .iter()
+                    .flat_map(|window| &window.panes)
+                    .map(|pane| pane.path.clone())
+                    .collect(),
+            );
+        }
+
+        if !self.tmux.has_session(session_name).unwrap_or(false) {
+            return None;
+        }
+
+        self.tmux
+            .list_session_panes(session_name, "#{pane_current_path}")
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod kill_tests {
+    use std::fs;
+
+    use mockall::{Sequence, predicate::eq};
+
+    use super::*;
+    use crate::{
+        recent::RecentImpl, session_name_file::MockSessionNameFile, sessions::SessionStorageImpl,
+        settings::RestoreSettings, test_utils::TempDir, tmux::MockTmux,
+    };
+
+    const SESSIONS: &str = r#"
+[work]
+windows = [
+    { index = 1, name = "code", layout = "", options = [], panes = [
+        { index = 1, path = "/src", active = true },
+    ] },
+]
+"#;
+
+    #[test]
+    fn should_switch_to_the_next_session_before_killing_the_current_one() {
+        // Given
+        let directory = TempDir::new();
+        let mut sequence = Sequence::new();
+        let mut tmux = MockTmux::new();
+        tmux.expect_has_session()
+            .with(eq("work"))
+            .returning(|_| Ok(true));
+        tmux.expect_list_sessions()
+            .returning(|_| Ok(vec!["work".to_string(), "main".to_string()]));
+        tmux.expect_current_session_name()
+            .returning(|| Ok("work".to_string()));
+        tmux.expect_select_session()
+            .with(eq("main"))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(()));
+        tmux.expect_kill_session()
+            .with(eq("work"))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(()));
+        tmux.expect_refresh_status().times(1).returning(|| Ok(()));
+        let mut file = MockSessionNameFile::new();
+        file.expect_read()
+            .returning(|| Ok(vec!["work".to_string(), "main".to_string()]));
+        file.expect_remove()
+            .with(eq("work"))
+            .times(1)
+            .returning(|_| Ok(()));
+        let sessions = SessionStorageImpl::new(
+            &directory.filename("sessions.toml"),
+            &tmux,
+            &RestoreSettings::default(),
+        );
+        let recent = RecentImpl::new(&tmux, &file);
+
+        // When
+        let result = SessionImpl::new(&tmux).kill("work", None, false, &sessions, &recent);
+
+        // Then
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn should_forget_a_stored_session_that_is_not_running() {
+        // Given
+        let directory = TempDir::new();
+        let filename = directory.filename("sessions.toml");
+        fs::write(&filename, SESSIONS).unwrap();
+        let mut tmux = MockTmux::new();
+        tmux.expect_has_session()
+            .with(eq("work"))
+            .returning(|_| Ok(false));
+        tmux.expect_kill_session().never();
+        let mut file = MockSessionNameFile::new();
+        file.expect_remove()
+            .with(eq("work"))
+            .times(1)
+            .returning(|_| Ok(()));
+        let sessions = SessionStorageImpl::new(&filename, &tmux, &RestoreSettings::default());
+        let recent = RecentImpl::new(&tmux, &file);
+
+        // When
+        let result = SessionImpl::new(&tmux).kill("work", None, true, &sessions, &recent);
+
+        // Then
+        assert!(result.is_ok());
+        assert!(!sessions.load().unwrap().contains_key("work"));
+    }
+
+    #[test]
+    fn should_fail_when_the_session_is_neither_running_nor_stored() {
+        // Given
+        let directory = TempDir::new();
+        let mut tmux = MockTmux::new();
+        tmux.expect_has_session()
+            .with(eq("work"))
+            .returning(|_| Ok(false));
+        let file = MockSessionNameFile::new();
+        let sessions = SessionStorageImpl::new(
+            &directory.filename("sessions.toml"),
+            &tmux,
+            &RestoreSettings::default(),
+        );
+        let recent = RecentImpl::new(&tmux, &file);
+
+        // When
+        let result = SessionImpl::new(&tmux).kill("work", None, true, &sessions, &recent);
+
+        // Then
+        assert!(matches!(result, Err(Error::NotFound(_))));
+    }
+}
+
+#[cfg(test)]
+mod rename_tests {
+    use std::fs;
+
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::{
+        scrollback::ScrollbackImpl,
+        session_name_file::MockSessionNameFile,
+        sessions::SessionStorageImpl,
+        settings::RestoreSettings,
+        template::{self, Templates, TemplatesImpl},
+        test_utils::TempDir,
+        tmux::MockTmux,
+    };
+
+    const SESSIONS: &str = r#"
+[work]
+windows = [
+    { index = 1, name = "code", layout = "", options = [], panes = [
+        { index = 1, path = "/src", active = true },
+    ] },
+]
+"#;
+
+    fn sessions_filename(directory: &TempDir) -> String {
+        let filename = directory.filename("sessions.toml");
+        fs::write(&filename, SESSIONS).unwrap();
+        filename
+    }
+
+    #[test]
+    fn should_rename_the_live_and_stored_session_and_its_pane_contents() {
+        // Given
+        let directory = TempDir::new();
+        let filename = sessions_filename(&directory);
+        let scrollback = ScrollbackImpl::new(&filename);
+        let pane = PaneContent {
+            window_index: 1,
+            pane_index: 1,
+            content: "output".to_string(),
+        };
+        scrollback.save("work", vec![pane], 1024).unwrap();
+        let mut tmux = MockTmux::new();
+        tmux.expect_has_session()
+            .with(eq("work"))
+            .returning(|_| Ok(true));
+        tmux.expect_has_session()
+            .with(eq("api_v2"))
+            .returning(|_| Ok(false));
+        tmux.expect_rename_session()
+            .with(eq("work"), eq("api_v2"))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let mut recent = MockSessionNameFile::new();
+        recent
+            .expect_rename()
+            .with(eq("work"), eq("api_v2"))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let sessions = SessionStorageImpl::new(&filename, &tmux, &RestoreSettings::default());
+
+        // When
+        let result =
+            SessionImpl::new(&tmux).rename("work", "api.v2", &sessions, &scrollback, &[&recent]);
+
+        // Then
+        assert_eq!(result.unwrap(), "api_v2");
+        let stored_names = sessions.load().unwrap().into_keys().collect::<Vec<_>>();
+        assert_eq!(stored_names, vec!["api_v2"]);
+        assert!(scrollback.replay_command("api_v2", 1, 1).is_some());
+        assert!(scrollback.replay_command("work", 1, 1).is_none());
+    }
+
+    #[test]
+    fn should_keep_the_new_name_when_the_template_is_applied_again() {
+        // Given
+        let directory = TempDir::new();
+        let templates_filename = directory.filename("templates.toml");
+        fs::write(
+            &templates_filename,
+            r#"
+[project]
+windows = [
+    { index = 1, name = "${name}", layout = "", options = [], panes = [
+        { index = 1, path = "/src/${name}", active = true },
+    ] },
+]
+"#,
+        )
+        .unwrap();
+        let templates = TemplatesImpl::new(&templates_filename);
+        let mut tmux = MockTmux::new();
+        tmux.expect_has_session()
+            .with(eq("api"))
+            .returning(|_| Ok(false));
+        tmux.expect_has_session()
+            .with(eq("web"))
+            .returning(|_| Ok(false));
+        let sessions = SessionStorageImpl::new(
+            &directory.filename("sessions.toml"),
+            &tmux,
+            &RestoreSettings::default(),
+        );
+        let session = SessionImpl::new(&tmux);
+        let variables = template::builtin_variables("api", "/src/api");
+        session
+            .update(
+                "api",
+                templates.instantiate("project", &variables).unwrap(),
+                &sessions,
+            )
+            .unwrap();
+
+        // When
+        session
+            .rename(
+                "api",
+                "web",
+                &sessions,
+                &ScrollbackImpl::new(&directory.filename("sessions.toml")),
+                &[],
+            )
+            .unwrap();
+        let link = sessions.load().unwrap()["web"].template.clone().unwrap();
+        let result = templates.instantiate(&link.name, &link.variables);
+
+        // Then
+        let window = &result.unwrap().windows[0];
+        assert_eq!(window.name, "web");
+        assert_eq!(window.panes[0].path, "/src/web");
+    }
+
+    #[test]
+    fn should_fail_for_an_unknown_session() {
+        // Given
+        let directory = TempDir::new();
+        let filename = sessions_filename(&directory);
+        let mut tmux = MockTmux::new();
+        tmux.expect_has_session().returning(|_| Ok(false));
+        let sessions = SessionStorageImpl::new(&filename, &tmux, &RestoreSettings::default());
+
+        // When
+        let result = SessionImpl::new(&tmux).rename(
+            "notes",
+            "new",
+            &sessions,
+            &ScrollbackImpl::new(&filename),
+            &[],
+        );
+
+        // Then
+        assert!(matches!(result, Err(Error::NotFound(_))));
+    }
+
+    #[test]
+    fn should_fail_if_the_new_name_is_taken() {
+        // Given
+        let directory = TempDir::new();
+        let filename = sessions_filename(&directory);
+        let mut tmux = MockTmux::new();
+        tmux.expect_has_session().returning(|_| Ok(true));
+        let sessions = SessionStorageImpl::new(&filename, &tmux, &RestoreSettings::default());
+
+        // When
+        let result = SessionImpl::new(&tmux).rename(
+            "work",
+            "main",
+            &sessions,
+            &ScrollbackImpl::new(&filename),
+            &[],
+        );
+
+        // Then
+        assert!(matches!(result, Err(Error::AlreadyExists(_))));
+        assert!(sessions.load().unwrap().contains_key("work"));
+    }
+}
+
+#[cfg(test)]
+mod clone_tests {
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::{
+        sessions::SessionStorageImpl, settings::RestoreSettings, test_utils::TempDir,
+        tmux::MockTmux,
+    };
+
+    #[test]
+    fn should_copy_a_live_session_that_was_never_saved() {
+        // Given
+        let directory = TempDir::new();
+        let mut tmux = MockTmux::new();
+        tmux.expect_has_session()
+            .with(eq("work"))
+            .returning(|_| Ok(true));
+        tmux.expect_has_session()
+            .with(eq("work_fix"))
+            .returning(|_| Ok(false));
+        tmux.expect_list_session_panes()
+            .withf(|session_name, format| {
+                session_name == "work" && format.starts_with("#{window_index}\t#{window_active}")
+            })
+            .returning(|_, _| {
+                Ok(vec![
+                    "1\t1\tb25d,80x24,0,0,1\t1\t1\t\tcode\t/src/api/web".to_string(),
+                ])
+            });
+        tmux.expect_list_session_panes()
+            .withf(|_, format| format.contains("#{pane_pid}"))
+            .returning(|_, _| Ok(vec![]));
+        tmux.expect_show_window_options()
+            .returning(|_, _| Ok(vec![]));
+        tmux.expect_show_session_options().returning(|_| Ok(vec![]));
+        let sessions = SessionStorageImpl::new(
+            &directory.filename("sessions.toml"),
+            &tmux,
+            &RestoreSettings::default(),
+        );
+        let rewrites = vec![utils::parse_path_rewrite("/src/api=/src/api-fix").unwrap()];
+
+        // When
+        let result = SessionImpl::new(&tmux).clone(
+            "work",
+            "work.fix",
+            &rewrites,
+            &sessions,
+            &SaveSettings::default(),
+        );
+
+        // Then
+        assert_eq!(result.unwrap(), "work_fix");
+        let stored = sessions.load().unwrap();
+        assert!(!stored.contains_key("work"));
+        let window = &stored["work_fix"].windows[0];
+        assert_eq!(window.name, "code");
+        assert_eq!(window.panes[0].path, "/src/api-fix/web");
     }
 }
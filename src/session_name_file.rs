@@ -1,15 +1,19 @@
 use std::io::BufRead;
-use std::io::Write;
 use std::path::Path;
 
 use mockall::automock;
 
+use crate::error::{Error, Result};
+use crate::state_file;
+
 #[automock]
 pub(crate) trait SessionNameFile {
-    fn read(&self) -> Vec<String>;
-    fn read_into(&self, vec: &mut Vec<String>, skip: &str);
-    fn append(&self, session_name: &str);
-    fn write(&self, session_names: &[String]);
+    fn read(&self) -> Result<Vec<String>>;
+    /// Moves the name to the top, adding it if it's not there yet.
+    fn prepend(&self, session_name: &str) -> Result<()>;
+    /// Replaces the name in place, dropping the new name if it was listed already.
+    fn rename(&self, session_name: &str, new_name: &str) -> Result<()>;
+    fn remove(&self, session_name: &str) -> Result<()>;
 }
 
 pub(crate) struct SessionNameFileImpl {
@@ -22,64 +26,97 @@ impl SessionNameFileImpl {
             filename: filename.to_string(),
         }
     }
-}
-
-impl SessionNameFile for SessionNameFileImpl {
-    fn read(&self) -> Vec<String> {
-        let path = Path::new(&self.filename);
 
-        if !path.exists() {
-            return Vec::new();
-        }
-
-        let file = std::fs::File::open(path).expect("Failed to open session name file.");
-        let reader = std::io::BufReader::new(file);
-        let lines = reader.lines();
+    /// Reads, transforms and writes the names while holding the file lock.
+    fn modify<F>(&self, f: F) -> Result<()>
+    where
+        F: FnOnce(Vec<String>) -> Vec<String>,
+    {
+        let _lock = state_file::lock(&self.filename)?;
+        let session_names = f(self.read()?);
+        let content: String = session_names
+            .iter()
+            .map(|name| format!("{}\n", name))
+            .collect();
 
-        lines
-            .map_while(Result::ok)
-            .filter(|line| !line.trim().is_empty())
-            .collect()
+        state_file::write(&self.filename, &content)
     }
+}
 
-    fn read_into(&self, vec: &mut Vec<String>, skip: &str) {
+impl SessionNameFile for SessionNameFileImpl {
+    fn read(&self) -> Result<Vec<String>> {
         let path = Path::new(&self.filename);
 
         if !path.exists() {
-            return;
+            return Ok(Vec::new());
         }
 
-        let file = std::fs::File::open(path).expect("Failed to open session name file.");
+        let file = std::fs::File::open(path).map_err(Error::io(&self.filename))?;
         let reader = std::io::BufReader::new(file);
         let lines = reader.lines();
 
-        let result: Vec<String> = lines
-            .map_while(Result::ok)
+        Ok(lines
+            .map_while(std::result::Result::ok)
             .filter(|line| !line.trim().is_empty())
-            .filter(|line| line != skip)
-            .collect();
-
-        for name in result {
-            vec.push(name);
-        }
+            .collect())
     }
 
-    fn append(&self, session_name: &str) {
-        let mut file = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(self.filename.clone())
-            .expect("Failed to open bookmarks file.");
+    fn prepend(&self, session_name: &str) -> Result<()> {
+        self.modify(|session_names| {
+            std::iter::once(session_name.to_string())
+                .chain(
+                    session_names
+                        .into_iter()
+                        .filter(|name| name != session_name),
+                )
+                .collect()
+        })
+    }
 
-        writeln!(file, "{}", session_name).expect("Failed to write to session name file.");
+    fn rename(&self, session_name: &str, new_name: &str) -> Result<()> {
+        self.modify(|session_names| {
+            session_names
+                .into_iter()
+                .filter(|name| name != new_name)
+                .map(|name| {
+                    if name == session_name {
+                        new_name.to_string()
+                    } else {
+                        name
+                    }
+                })
+                .collect()
+        })
     }
 
-    fn write(&self, session_names: &[String]) {
-        let mut file = std::fs::File::create(self.filename.clone())
-            .expect("Failed to create session name file.");
+    fn remove(&self, session_name: &str) -> Result<()> {
+        self.modify(|session_names| {
+            session_names
+                .into_iter()
+                .filter(|name| name != session_name)
+                .collect()
+        })
+    }
+}
 
-        for session_name in session_names {
-            writeln!(file, "{}", session_name).expect("Failed to write to session name file.");
-        }
+#[cfg(test)]
+mod rename_tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+
+    #[test]
+    fn should_rename_in_place() {
+        // Given
+        let directory = TempDir::new();
+        let filename = directory.filename("recent_sessions");
+        std::fs::write(&filename, "main\nwork\nnotes\nnew\n").unwrap();
+        let file = SessionNameFileImpl::new(&filename);
+
+        // When
+        file.rename("work", "new").unwrap();
+
+        // Then
+        let result = file.read().unwrap();
+        assert_eq!(result, vec!["main", "new", "notes"]);
     }
 }
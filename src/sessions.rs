@@ -1,31 +1,134 @@
-use std::{collections::HashMap, fs, thread::sleep, time::Duration};
+use std::{collections::HashMap, fs, io::ErrorKind, path::Path, thread::sleep, time::Duration};
 
 use crate::{
-    model::{Layout, SessionName, TmuxSession, TmuxSessions, TmuxWindows},
+    error::{Error, Result},
+    layout::{self, LayoutCell, LayoutContent, SplitDirection},
+    model::{
+        EnvironmentVariable, Layout, SessionName, SessionSummary, TmuxPane, TmuxSession,
+        TmuxSessions, TmuxWindow, TmuxWindows, WindowName,
+    },
+    scrollback::{Scrollback, ScrollbackImpl},
+    settings::RestoreSettings,
+    snapshots::{Snapshots, SnapshotsImpl},
+    state_file,
     tmux::{SplitWindowOptions, Tmux},
     utils,
 };
 
+const HISTORY_DIRNAME: &str = "history";
+const HISTORY_SIZE: usize = 50;
+
+pub(crate) struct Changes {
+    pub(crate) added: Vec<SessionName>,
+    pub(crate) removed: Vec<SessionName>,
+    pub(crate) changed: Vec<SessionName>,
+}
+
+/// A saved window whose layout tmux would reject.
+pub(crate) struct InvalidLayout {
+    pub(crate) session_name: SessionName,
+    pub(crate) window_name: WindowName,
+    /// The layout with a corrected checksum, if it's otherwise well formed.
+    pub(crate) repaired: Option<String>,
+}
+
+/// A previous state of the sessions file. `number` 1 is the most recent one.
+pub(crate) struct Version {
+    pub(crate) number: usize,
+    /// Milliseconds since the Unix epoch.
+    pub(crate) timestamp: u128,
+    /// What changed between this version and the one that replaced it.
+    pub(crate) changes: Changes,
+}
+
 pub(crate) trait SessionStorage {
-    fn save(&self, sessions: TmuxSessions);
-    fn restore_all(&self);
-    fn restore(&self, session_name: &str) -> Option<bool>;
-    fn load(&self) -> TmuxSessions;
-    fn list(&self) -> Vec<SessionName>;
-    fn convert(&self, output: &str);
+    fn save(&self, sessions: TmuxSessions) -> Result<()>;
+    /// Loads, modifies and saves the sessions while holding the file lock.
+    fn modify(&self, f: &mut dyn FnMut(&mut TmuxSessions)) -> Result<()>;
+    fn history(&self) -> Result<Vec<Version>>;
+    /// Returns `false` if there is no such version.
+    fn rollback(&self, version: usize) -> Result<bool>;
+    fn restore_all(&self) -> Result<()>;
+    fn restore(&self, session_name: &str) -> Result<Option<bool>>;
+    fn load(&self) -> Result<TmuxSessions>;
+    fn convert(&self, output: &str) -> Result<()>;
+    /// Finds saved windows with layouts tmux would reject and, if `fix` is set, stores the
+    /// repaired ones.
+    fn check_layouts(&self, fix: bool) -> Result<Vec<InvalidLayout>>;
 }
 
 pub(crate) struct SessionStorageImpl<'t, T: Tmux> {
     filename: String,
     tmux: &'t T,
+    restore_settings: RestoreSettings,
+    scrollback: Option<ScrollbackImpl>,
+    history: SnapshotsImpl,
 }
 
 impl<'t, T: Tmux> SessionStorageImpl<'t, T> {
-    pub(crate) fn new(filename: &str, tmux: &'t T) -> Self {
+    pub(crate) fn new(filename: &str, tmux: &'t T, restore_settings: &RestoreSettings) -> Self {
+        // Previous versions are kept next to the sessions file, e.g. history/sessions-<ts>.toml.
+        let path = Path::new(filename);
+        let history_dirname = path.with_file_name(HISTORY_DIRNAME);
+        let prefix = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("sessions");
+
         Self {
             filename: filename.to_string(),
             tmux,
+            restore_settings: restore_settings.clone(),
+            scrollback: Some(ScrollbackImpl::new(filename)),
+            history: SnapshotsImpl::new(&history_dirname.to_string_lossy(), prefix),
+        }
+    }
+
+    /// Restores panes without replaying their stored contents, which only exist on this machine.
+    pub(crate) fn without_scrollback(mut self) -> Self {
+        self.scrollback = None;
+        self
+    }
+
+    /// Expects the caller to hold the file lock.
+    fn write(&self, sessions: &TmuxSessions) -> Result<()> {
+        let toml_string =
+            toml::to_string(sessions).map_err(|error| Error::parse(&self.filename, error))?;
+
+        if let Ok(previous) = fs::read_to_string(&self.filename)
+            && previous != toml_string
+        {
+            self.history.write(&previous)?;
+            self.history.prune(HISTORY_SIZE);
         }
+
+        state_file::write(&self.filename, &toml_string)?;
+        eprintln!("TMUX sessions saved to {} file.", &self.filename);
+
+        Ok(())
+    }
+
+    fn parse(&self, filename: &str, content: &str) -> Result<TmuxSessions> {
+        toml::from_str(content).map_err(|error| Error::parse(filename, error.message()))
+    }
+
+    fn read(&self, filename: &Path) -> Result<TmuxSessions> {
+        let content = fs::read_to_string(filename).map_err(Error::io(filename.display()))?;
+        self.parse(&filename.to_string_lossy(), &content)
+    }
+
+    /// Stored pane contents are replayed only into panes without a startup command.
+    fn startup_command(
+        &self,
+        session_name: &str,
+        window: &TmuxWindow,
+        pane: &TmuxPane,
+    ) -> Option<String> {
+        pane.startup_command.clone().or_else(|| {
+            self.scrollback
+                .as_ref()?
+                .replay_command(session_name, window.index, pane.index)
+        })
     }
 
     fn restore_session(
@@ -33,170 +136,281 @@ impl<'t, T: Tmux> SessionStorageImpl<'t, T> {
         session_name: &str,
         session: &TmuxSession,
         windows_to_layout: &mut Vec<Layout>,
-    ) {
+    ) -> Result<()> {
         if session.windows.is_empty() {
-            return;
+            return Ok(());
         }
 
-        for (i, tmux_window) in session.windows.iter().enumerate() {
-            if i == 0
-                && let Some(first_pane) = tmux_window.panes.first()
-            {
-                self.tmux
-                    .new_session(session_name, tmux_window, &first_pane.startup_command);
-
-                for option in &tmux_window.options {
-                    self.tmux.set_window_option(tmux_window.name.as_str(), option);
-                }
-
-                if first_pane.startup_command.is_none()
-                    && let Some(shell_command) = &first_pane.shell_command
-                {
-                    self.tmux
-                        .send_keys(session_name, &tmux_window.name, 1, shell_command);
-                }
-
-                if tmux_window.panes.len() > 1 {
-                    for (i, pane) in tmux_window.panes.iter().enumerate().skip(1) {
-                        self.tmux.split_window(
-                            session_name,
-                            &tmux_window.name,
-                            &SplitWindowOptions {
-                                horizontally: true,
-                                path: Some(pane.path.clone()),
-                                startup_command: pane.startup_command.clone(),
-                                at_index: None,
-                                before: false,
-                            },
-                        );
-
-                        if let Some(name) = &pane.name {
-                            self.tmux.set_pane_option_for_current_window(
-                                i + 1,
-                                "@window-name",
-                                name,
-                            );
-                        };
-
-                        if pane.startup_command.is_none()
-                            && let Some(shell_command) = &pane.shell_command
-                        {
-                            self.tmux.send_keys(
-                                session_name,
-                                &tmux_window.name,
-                                i + 1,
-                                shell_command,
-                            );
-                        }
-                    }
+        // Any pane of the window to select last, the first window unless another one was active.
+        let mut active_window_pane_id = None;
 
-                    windows_to_layout.push(Layout {
-                        session_name: session_name.to_string(),
-                        window_name: tmux_window.name.clone(),
-                        layout: tmux_window.layout.clone(),
-                    });
-                }
-            } else if i > 0
-                && let Some(pane) = tmux_window.panes.first()
-            {
+        for (i, tmux_window) in session.windows.iter().enumerate() {
+            let Some(first_pane) = tmux_window.panes.first() else {
+                continue;
+            };
+            let startup_command = self.startup_command(session_name, tmux_window, first_pane);
+            let environment =
+                utils::inherit_environment(&session.environment, &tmux_window.environment);
+            let first_pane_environment =
+                utils::inherit_environment(&environment, &first_pane.environment);
+
+            let first_pane_id = if i == 0 {
+                self.tmux.new_session(
+                    session_name,
+                    tmux_window,
+                    &first_pane_environment,
+                    &startup_command,
+                )?
+            } else {
                 self.tmux.new_window(
                     session_name,
                     tmux_window.name.as_str(),
-                    &Some(pane.path.clone()),
-                    &pane.environment,
-                    &pane.startup_command,
+                    &Some(first_pane.path.clone()),
+                    &first_pane_environment,
+                    &startup_command,
                     false,
-                );
+                )?
+            };
+
+            for option in &tmux_window.options {
+                self.tmux
+                    .set_window_option(tmux_window.name.as_str(), option)?;
+            }
+
+            let pane_ids =
+                self.restore_panes(session_name, tmux_window, &environment, first_pane_id)?;
 
-                for option in &tmux_window.options {
-                    self.tmux.set_window_option(tmux_window.name.as_str(), option);
+            for (pane, pane_id) in tmux_window.panes.iter().zip(&pane_ids) {
+                if let Some(name) = &pane.name {
+                    self.tmux
+                        .set_pane_option_by_id(pane_id, "@window-name", name)?;
                 }
 
                 if pane.startup_command.is_none()
                     && let Some(shell_command) = &pane.shell_command
                 {
-                    self.tmux
-                        .send_keys(session_name, &tmux_window.name, 1, shell_command);
+                    self.tmux.send_keys_to_pane(pane_id, shell_command)?;
                 }
+            }
 
-                if tmux_window.panes.len() > 1 {
-                    for pane in tmux_window.panes.iter().skip(1) {
-                        let command = tmux_window.startup_command_for_pane(i + 1);
-                        self.tmux.split_window(
-                            session_name,
-                            &tmux_window.name,
-                            &SplitWindowOptions {
-                                horizontally: true,
-                                path: Some(pane.path.clone()),
-                                startup_command: command.clone(),
-                                at_index: None,
-                                before: false,
-                            },
-                        );
+            if let Some((_, pane_id)) = tmux_window
+                .panes
+                .iter()
+                .zip(&pane_ids)
+                .find(|(pane, _)| pane.active)
+            {
+                self.tmux.select_pane_by_id(pane_id)?;
+            }
 
-                        if let Some(name) = &pane.name {
-                            self.tmux.set_pane_option_for_current_window(
-                                i + 1,
-                                "@window-name",
-                                name,
-                            );
-                        };
-
-                        if command.is_none()
-                            && let Some(shell_command) = &pane.shell_command
-                        {
-                            self.tmux.send_keys(
-                                session_name,
-                                &tmux_window.name,
-                                i + 1,
-                                shell_command,
-                            );
-                        }
+            if tmux_window.active == Some(true) || active_window_pane_id.is_none() {
+                active_window_pane_id = pane_ids.first().cloned();
+            }
+
+            if tmux_window.panes.len() > 1 {
+                let layout = match layout::check(&tmux_window.layout) {
+                    layout::Check::Valid => Some(tmux_window.layout.clone()),
+                    layout::Check::Repairable(layout) => Some(layout),
+                    layout::Check::Invalid => {
+                        eprintln!(
+                            "Ignoring invalid layout of window '{}' in session '{}'.",
+                            tmux_window.name, session_name
+                        );
+                        None
                     }
+                };
 
+                if let Some(layout) = layout {
                     windows_to_layout.push(Layout {
                         session_name: session_name.to_string(),
                         window_name: tmux_window.name.clone(),
-                        layout: tmux_window.layout.clone(),
+                        layout,
                     });
                 }
             }
         }
 
         for option in &session.options {
-            self.tmux.set_session_option(session_name, option);
+            self.tmux.set_session_option(session_name, option)?;
+        }
+
+        if let Some(pane_id) = &active_window_pane_id {
+            self.tmux.select_window_by_pane_id(pane_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Splits the first pane of a window into the rest of its panes, following the saved layout
+    /// when it matches them. `environment` is what the panes inherit from the session and window.
+    /// Returns the pane ids in pane index order.
+    fn restore_panes(
+        &self,
+        session_name: &str,
+        window: &TmuxWindow,
+        environment: &[EnvironmentVariable],
+        first_pane_id: String,
+    ) -> Result<Vec<String>> {
+        if let Some(cell) = layout::parse(&window.layout)
+            && cell.pane_count() == window.panes.len()
+        {
+            return self.split_cell(session_name, window, environment, &cell, first_pane_id, 0);
+        }
+
+        let mut pane_ids = vec![first_pane_id];
+
+        for pane in window.panes.iter().skip(1) {
+            let options = self.split_options(session_name, window, environment, pane, true, None);
+            let pane_id = self
+                .tmux
+                .split_pane(&pane_ids[pane_ids.len() - 1], &options)?;
+            pane_ids.push(pane_id);
+        }
+
+        Ok(pane_ids)
+    }
+
+    /// Recreates the splits of a layout cell inside the given pane. `first_pane` is the index into
+    /// the window panes of the cell's first pane.
+    fn split_cell(
+        &self,
+        session_name: &str,
+        window: &TmuxWindow,
+        environment: &[EnvironmentVariable],
+        cell: &LayoutCell,
+        pane_id: String,
+        first_pane: usize,
+    ) -> Result<Vec<String>> {
+        let LayoutContent::Split(direction, children) = &cell.content else {
+            return Ok(vec![pane_id]);
+        };
+
+        // Each child is split off the previous one, taking the space of all the children after it.
+        let mut child_panes = vec![(pane_id, first_pane)];
+        let mut next_pane = first_pane + children[0].pane_count();
+
+        for (i, child) in children.iter().enumerate().skip(1) {
+            let remaining = &children[i..];
+            let size =
+                remaining.iter().map(|c| c.size(*direction)).sum::<usize>() + remaining.len() - 1;
+            let options = self.split_options(
+                session_name,
+                window,
+                environment,
+                &window.panes[next_pane],
+                *direction == SplitDirection::Horizontal,
+                Some(size),
+            );
+            let previous_pane_id = &child_panes[child_panes.len() - 1].0;
+            let pane_id = self.tmux.split_pane(previous_pane_id, &options)?;
+
+            child_panes.push((pane_id, next_pane));
+            next_pane += child.pane_count();
         }
 
-        self.tmux.select_window(session_name, 1)
+        let mut pane_ids = Vec::new();
+
+        for (child, (pane_id, first_pane)) in children.iter().zip(child_panes) {
+            pane_ids.extend(self.split_cell(
+                session_name,
+                window,
+                environment,
+                child,
+                pane_id,
+                first_pane,
+            )?);
+        }
+
+        Ok(pane_ids)
+    }
+
+    fn split_options(
+        &self,
+        session_name: &str,
+        window: &TmuxWindow,
+        environment: &[EnvironmentVariable],
+        pane: &TmuxPane,
+        horizontally: bool,
+        size: Option<usize>,
+    ) -> SplitWindowOptions {
+        SplitWindowOptions {
+            horizontally,
+            path: Some(pane.path.clone()),
+            startup_command: self.startup_command(session_name, window, pane),
+            at_index: None,
+            before: false,
+            size,
+            environment: utils::inherit_environment(environment, &pane.environment),
+        }
     }
 
-    fn restore_layouts(&self, windows_to_layout: &Vec<Layout>, delay_in_millis: u64) {
+    fn restore_layouts(&self, windows_to_layout: &Vec<Layout>, delay_in_millis: u64) -> Result<()> {
         if !windows_to_layout.is_empty() {
             if delay_in_millis > 0 {
                 sleep(Duration::from_millis(delay_in_millis)); // To remove WSL quirks.
             }
 
             for layout in windows_to_layout {
-                self.tmux
-                    .select_layout(&layout.session_name, &layout.window_name, &layout.layout);
+                self.tmux.select_layout(
+                    &layout.session_name,
+                    &layout.window_name,
+                    &layout.layout,
+                )?;
             }
         }
+
+        Ok(())
     }
 }
 
 impl<'t, T: Tmux> SessionStorage for SessionStorageImpl<'t, T> {
-    fn save(&self, sessions: TmuxSessions) {
-        let toml_string =
-            toml::to_string(&sessions).expect("Failed to serialize sessions into TOML.");
+    fn save(&self, sessions: TmuxSessions) -> Result<()> {
+        let _lock = state_file::lock(&self.filename)?;
+        self.write(&sessions)
+    }
 
-        fs::write(&self.filename, toml_string)
-            .unwrap_or_else(|_| panic!("Failed to write to {}.", &self.filename));
-        eprintln!("TMUX sessions saved to {} file.", &self.filename);
+    fn modify(&self, f: &mut dyn FnMut(&mut TmuxSessions)) -> Result<()> {
+        let _lock = state_file::lock(&self.filename)?;
+        let mut sessions = self.load()?;
+        f(&mut sessions);
+        self.write(&sessions)
+    }
+
+    fn history(&self) -> Result<Vec<Version>> {
+        let mut newer = self.load()?;
+
+        self.history
+            .list()
+            .into_iter()
+            .enumerate()
+            .map(|(i, snapshot)| {
+                let sessions = self.read(&snapshot.filename)?;
+                let changes = changes(&sessions, &newer);
+                newer = sessions;
+
+                Ok(Version {
+                    number: i + 1,
+                    timestamp: snapshot.timestamp,
+                    changes,
+                })
+            })
+            .collect()
     }
 
-    fn restore_all(&self) {
+    fn rollback(&self, version: usize) -> Result<bool> {
+        let Some(snapshot) = version
+            .checked_sub(1)
+            .and_then(|i| self.history.list().into_iter().nth(i))
+        else {
+            return Ok(false);
+        };
+
+        self.save(self.read(&snapshot.filename)?)?;
+        Ok(true)
+    }
+
+    fn restore_all(&self) -> Result<()> {
         eprintln!("Restoring TMUX sessions from {} file...", &self.filename);
-        let sessions = self.load();
+        let sessions = self.load()?;
         // eprintln!("sessions: {:?}", sessions);
 
         let mut windows_to_layout = Vec::new();
@@ -204,53 +418,59 @@ impl<'t, T: Tmux> SessionStorage for SessionStorageImpl<'t, T> {
         for (name, session) in sessions {
             let non_numeric = !utils::is_numeric(name.as_str());
 
-            if !session.windows.is_empty() && non_numeric && !self.tmux.has_session(name.as_str()) {
-                self.restore_session(name.as_str(), &session, &mut windows_to_layout);
+            if !session.windows.is_empty()
+                && non_numeric
+                && !self.tmux.has_session(name.as_str())?
+            {
+                self.restore_session(name.as_str(), &session, &mut windows_to_layout)?;
             }
         }
 
-        self.restore_layouts(&windows_to_layout, 300);
+        self.restore_layouts(
+            &windows_to_layout,
+            self.restore_settings.layout_delay_millis,
+        )
     }
 
-    fn load(&self) -> HashMap<SessionName, TmuxSession> {
-        let file_content = fs::read_to_string(&self.filename);
-
-        match file_content {
-            Ok(content) => toml::from_str(&content).unwrap_or_else(|error| {
-                panic!("Failed to parse {}: {}.", &self.filename, error.message())
-            }),
-            Err(_) => HashMap::new(),
+    fn load(&self) -> Result<HashMap<SessionName, TmuxSession>> {
+        match fs::read_to_string(&self.filename) {
+            Ok(content) => self.parse(&self.filename, &content),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(error) => Err(Error::io(&self.filename)(error)),
         }
     }
 
     // The return bool value indicates whether the session was spawned in the background. Yeah, I
     // know returning optional bool doesn't say shit. I'm lazy.
-    fn restore(&self, session_name: &str) -> Option<bool> {
-        let sessions = self.load();
+    fn restore(&self, session_name: &str) -> Result<Option<bool>> {
+        let sessions = self.load()?;
 
-        sessions.get(session_name).map(|session| {
-            let mut windows_to_layout = Vec::new();
-            self.restore_session(session_name, session, &mut windows_to_layout);
-            self.restore_layouts(&windows_to_layout, 300);
+        let Some(session) = sessions.get(session_name) else {
+            return Ok(None);
+        };
 
-            if !session.windows.is_empty()
-                && let Some(background) = session.background
-            {
-                return background;
-            }
+        let mut windows_to_layout = Vec::new();
+        self.restore_session(session_name, session, &mut windows_to_layout)?;
+        self.restore_layouts(
+            &windows_to_layout,
+            self.restore_settings.layout_delay_millis,
+        )?;
+
+        if !session.windows.is_empty()
+            && let Some(background) = session.background
+        {
+            return Ok(Some(background));
+        }
 
-            false
-        })
+        Ok(Some(false))
     }
 
-    fn convert(&self, output: &str) {
+    fn convert(&self, output: &str) -> Result<()> {
         let file_content = fs::read_to_string(&self.filename);
 
         if let Ok(content) = file_content {
-            let old: HashMap<SessionName, TmuxWindows> =
-                toml::from_str(&content).unwrap_or_else(|error| {
-                    panic!("Failed to parse {}: {}.", &self.filename, error.message())
-                });
+            let old: HashMap<SessionName, TmuxWindows> = toml::from_str(&content)
+                .map_err(|error| Error::parse(&self.filename, error.message()))?;
 
             let new: HashMap<SessionName, TmuxSession> = old
                 .into_iter()
@@ -262,33 +482,498 @@ impl<'t, T: Tmux> SessionStorage for SessionStorageImpl<'t, T> {
                             no_recent_tracking: None,
                             windows,
                             options: vec![],
+                            environment: vec![],
+                            template: None,
                         },
                     )
                 })
                 .collect();
 
-            let toml_string =
-                toml::to_string(&new).expect("Failed to serialize sessions into TOML.");
+            let toml_string = toml::to_string(&new).map_err(|error| Error::parse(output, error))?;
 
-            fs::write(output, toml_string)
-                .unwrap_or_else(|_| panic!("Failed to write to {}.", &output));
+            fs::write(output, toml_string).map_err(Error::io(output))?;
             eprintln!("TMUX sessions saved to {} file.", &output);
         }
-    }
 
-    fn list(&self) -> Vec<SessionName> {
-        let file_content = fs::read_to_string(&self.filename);
+        Ok(())
+    }
 
-        match file_content {
-            Ok(content) => {
-                let sessions: HashMap<SessionName, TmuxSession> = toml::from_str(&content)
-                    .unwrap_or_else(|error| {
-                        panic!("Failed to parse {}: {}.", &self.filename, error.message())
-                    });
+    fn check_layouts(&self, fix: bool) -> Result<Vec<InvalidLayout>> {
+        let _lock = state_file::lock(&self.filename)?;
+        let mut sessions = self.load()?;
+        let mut invalid_layouts = Vec::new();
+
+        for (session_name, session) in sessions.iter_mut() {
+            for window in session.windows.iter_mut() {
+                let repaired = match layout::check(&window.layout) {
+                    layout::Check::Valid => continue,
+                    layout::Check::Repairable(layout) => Some(layout),
+                    layout::Check::Invalid => None,
+                };
+
+                if fix && let Some(layout) = &repaired {
+                    window.layout = layout.clone();
+                }
 
-                sessions.keys().cloned().collect()
+                invalid_layouts.push(InvalidLayout {
+                    session_name: session_name.clone(),
+                    window_name: window.name.clone(),
+                    repaired,
+                });
             }
-            Err(_) => Vec::new(),
         }
+
+        if fix && invalid_layouts.iter().any(|l| l.repaired.is_some()) {
+            self.write(&sessions)?;
+        }
+
+        invalid_layouts.sort_by(|a, b| a.session_name.cmp(&b.session_name));
+        Ok(invalid_layouts)
+    }
+}
+
+/// The `sessions list` entries of the stored sessions, sorted by name.
+pub(crate) fn summaries(
+    sessions: TmuxSessions,
+    live_names: &[SessionName],
+    bookmarked_names: &[SessionName],
+    recent_names: &[SessionName],
+) -> Vec<SessionSummary> {
+    let mut summaries = sessions
+        .into_iter()
+        .map(|(name, session)| SessionSummary {
+            windows: session.windows.len(),
+            panes: session.windows.iter().map(|w| w.panes.len()).sum(),
+            live: live_names.contains(&name),
+            bookmarked: bookmarked_names.contains(&name),
+            recent: recent_names.contains(&name),
+            name,
+        })
+        .collect::<Vec<_>>();
+    summaries.sort_by_key(|summary| summary.name.to_lowercase());
+    summaries
+}
+
+/// Compares two versions of the sessions file by session name.
+fn changes(old: &TmuxSessions, new: &TmuxSessions) -> Changes {
+    let mut added: Vec<SessionName> = new
+        .keys()
+        .filter(|name| !old.contains_key(*name))
+        .cloned()
+        .collect();
+    let mut removed: Vec<SessionName> = old
+        .keys()
+        .filter(|name| !new.contains_key(*name))
+        .cloned()
+        .collect();
+    let mut changed: Vec<SessionName> = old
+        .iter()
+        .filter(|(name, session)| new.get(*name).is_some_and(|s| s != *session))
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    added.sort();
+    removed.sort();
+    changed.sort();
+
+    Changes {
+        added,
+        removed,
+        changed,
+    }
+}
+
+#[cfg(test)]
+mod load_tests {
+    use super::*;
+    use crate::{test_utils::TempDir, tmux::MockTmux};
+
+    #[test]
+    fn should_return_a_parse_error_for_a_malformed_sessions_file() {
+        // Given
+        let directory = TempDir::new();
+        let filename = directory.filename("sessions.toml");
+        fs::write(&filename, "[broken").unwrap();
+        let tmux = MockTmux::new();
+        let sessions = SessionStorageImpl::new(&filename, &tmux, &RestoreSettings::default());
+
+        // When
+        let result = sessions.load();
+
+        // Then
+        assert!(matches!(result, Err(Error::Parse { .. })));
+    }
+}
+
+#[cfg(test)]
+mod history_tests {
+    use super::*;
+    use crate::{test_utils::TempDir, tmux::MockTmux};
+
+    const SESSION: &str = r#"
+windows = [
+    { index = 1, name = "code", layout = "", options = [], panes = [
+        { index = 1, path = "PATH", active = true },
+    ] },
+]
+"#;
+
+    /// Sessions by name, each with a single pane in the path.
+    fn stored(sessions: &[(&str, &str)]) -> TmuxSessions {
+        sessions
+            .iter()
+            .map(|(name, path)| {
+                let session = toml::from_str(&SESSION.replace("PATH", path)).unwrap();
+                (name.to_string(), session)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_keep_the_replaced_version_in_the_history() {
+        // Given
+        let directory = TempDir::new();
+        let tmux = MockTmux::new();
+        let sessions = SessionStorageImpl::new(
+            &directory.filename("sessions.toml"),
+            &tmux,
+            &RestoreSettings::default(),
+        );
+        sessions.save(stored(&[("work", "/src")])).unwrap();
+
+        // When
+        sessions.save(stored(&[("work", "/src/api")])).unwrap();
+
+        // Then
+        let versions = sessions.history().unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].number, 1);
+        assert_eq!(versions[0].changes.changed, vec!["work"]);
+        let backups = fs::read_dir(directory.path().join(HISTORY_DIRNAME))
+            .unwrap()
+            .count();
+        assert_eq!(backups, 1);
+    }
+
+    #[test]
+    fn should_prune_the_history_to_its_size() {
+        // Given
+        let directory = TempDir::new();
+        let tmux = MockTmux::new();
+        let sessions = SessionStorageImpl::new(
+            &directory.filename("sessions.toml"),
+            &tmux,
+            &RestoreSettings::default(),
+        );
+
+        // When
+        for i in 0..HISTORY_SIZE + 3 {
+            sessions
+                .save(stored(&[("work", &format!("/src/{}", i))]))
+                .unwrap();
+        }
+
+        // Then
+        assert_eq!(sessions.history().unwrap().len(), HISTORY_SIZE);
+    }
+
+    #[test]
+    fn should_report_added_removed_and_changed_sessions() {
+        // Given
+        let old = stored(&[("main", "/"), ("notes", "/notes"), ("work", "/src")]);
+        let new = stored(&[("api", "/src/api"), ("main", "/"), ("work", "/src/web")]);
+
+        // When
+        let result = changes(&old, &new);
+
+        // Then
+        assert_eq!(result.added, vec!["api"]);
+        assert_eq!(result.removed, vec!["notes"]);
+        assert_eq!(result.changed, vec!["work"]);
+    }
+
+    #[test]
+    fn should_roll_back_to_a_previous_version() {
+        // Given
+        let directory = TempDir::new();
+        let tmux = MockTmux::new();
+        let sessions = SessionStorageImpl::new(
+            &directory.filename("sessions.toml"),
+            &tmux,
+            &RestoreSettings::default(),
+        );
+        sessions.save(stored(&[("work", "/src")])).unwrap();
+        sessions.save(stored(&[("work", "/src/api")])).unwrap();
+        sessions.save(stored(&[("notes", "/notes")])).unwrap();
+
+        // When
+        let result = sessions.rollback(2);
+
+        // Then
+        assert!(result.unwrap());
+        assert_eq!(sessions.load().unwrap(), stored(&[("work", "/src")]));
+        assert!(!sessions.rollback(10).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod summaries_tests {
+    use super::*;
+
+    const SESSIONS: &str = r#"
+[work]
+windows = [
+    { index = 1, name = "code", layout = "", options = [], panes = [
+        { index = 1, path = "/src", active = true },
+        { index = 2, path = "/src", active = false },
+    ] },
+    { index = 2, name = "logs", layout = "", options = [], panes = [
+        { index = 1, path = "/var/log", active = true },
+    ] },
+]
+
+[Main]
+windows = []
+
+[notes]
+windows = []
+"#;
+
+    #[test]
+    fn should_count_windows_and_panes_and_flag_live_bookmarked_and_recent_sessions() {
+        // Given
+        let sessions = toml::from_str(SESSIONS).unwrap();
+        let live_names = vec!["work".to_string(), "scratch".to_string()];
+        let bookmarked_names = vec!["notes".to_string()];
+        let recent_names = vec!["Main".to_string(), "work".to_string()];
+
+        // When
+        let result = summaries(sessions, &live_names, &bookmarked_names, &recent_names);
+
+        // Then
+        let result = result
+            .iter()
+            .map(|s| {
+                (
+                    s.name.as_str(),
+                    s.windows,
+                    s.panes,
+                    s.live,
+                    s.bookmarked,
+                    s.recent,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            result,
+            vec![
+                ("Main", 0, 0, false, false, true),
+                ("notes", 0, 0, false, true, false),
+                ("work", 2, 3, true, false, true),
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod restore_tests {
+    use super::*;
+    use crate::tmux::MockTmux;
+    use mockall::{Sequence, predicate::eq};
+
+    fn pane(index: usize) -> TmuxPane {
+        TmuxPane {
+            index,
+            path: "/tmp".to_string(),
+            active: index == 1,
+            startup_command: None,
+            shell_command: None,
+            environment: vec![],
+            name: None,
+        }
+    }
+
+    #[test]
+    fn should_split_panes_following_the_saved_layout() {
+        // Given
+        let session = TmuxSession {
+            background: None,
+            no_recent_tracking: None,
+            windows: vec![TmuxWindow {
+                index: 1,
+                name: "main".to_string(),
+                layout: "5c51,80x24,0,0{40x24,0,0,1,39x24,41,0[39x12,41,0,2,39x11,41,13,3]}"
+                    .to_string(),
+                panes: vec![pane(1), pane(2), pane(3)],
+                options: vec![],
+                active: Some(true),
+                environment: vec![],
+            }],
+            options: vec![],
+            environment: vec![],
+            template: None,
+        };
+        let mut tmux = MockTmux::new();
+        let mut sequence = Sequence::new();
+        tmux.expect_new_session()
+            .returning(|_, _, _, _| Ok("%0".to_string()));
+        tmux.expect_split_pane()
+            .withf(|pane_id, options| {
+                pane_id == "%0" && options.horizontally && options.size == Some(39)
+            })
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _| Ok("%1".to_string()));
+        tmux.expect_split_pane()
+            .withf(|pane_id, options| {
+                pane_id == "%1" && !options.horizontally && options.size == Some(11)
+            })
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _| Ok("%2".to_string()));
+        tmux.expect_select_pane_by_id()
+            .with(eq("%0"))
+            .returning(|_| Ok(()));
+        tmux.expect_select_window_by_pane_id()
+            .with(eq("%0"))
+            .returning(|_| Ok(()));
+        let sessions = SessionStorageImpl::new(
+            "/nonexistent/sessions.toml",
+            &tmux,
+            &RestoreSettings::default(),
+        );
+        let mut windows_to_layout = Vec::new();
+
+        // When
+        let result = sessions.restore_session("test", &session, &mut windows_to_layout);
+
+        // Then
+        assert!(result.is_ok());
+        assert_eq!(windows_to_layout.len(), 1);
+    }
+
+    #[test]
+    fn should_select_the_saved_active_window_and_panes() {
+        // Given
+        let window = |index: usize, active_pane: usize| TmuxWindow {
+            index,
+            name: format!("window-{}", index),
+            layout: "0000,80x24,0,0{40x24,0,0,1,39x24,41,0,2}".to_string(),
+            panes: vec![
+                TmuxPane {
+                    active: active_pane == 1,
+                    ..pane(1)
+                },
+                TmuxPane {
+                    active: active_pane == 2,
+                    ..pane(2)
+                },
+            ],
+            options: vec![],
+            active: Some(index == 2),
+            environment: vec![],
+        };
+        let session = TmuxSession {
+            background: None,
+            no_recent_tracking: None,
+            windows: vec![window(1, 1), window(2, 2)],
+            options: vec![],
+            environment: vec![],
+            template: None,
+        };
+        let mut tmux = MockTmux::new();
+        tmux.expect_new_session()
+            .returning(|_, _, _, _| Ok("%0".to_string()));
+        tmux.expect_new_window()
+            .returning(|_, _, _, _, _, _| Ok("%2".to_string()));
+        tmux.expect_split_pane()
+            .returning(|pane_id, _| Ok(if pane_id == "%0" { "%1" } else { "%3" }.to_string()));
+        tmux.expect_select_pane_by_id()
+            .with(eq("%0"))
+            .times(1)
+            .returning(|_| Ok(()));
+        tmux.expect_select_pane_by_id()
+            .with(eq("%3"))
+            .times(1)
+            .returning(|_| Ok(()));
+        tmux.expect_select_window_by_pane_id()
+            .with(eq("%2"))
+            .times(1)
+            .returning(|_| Ok(()));
+        let sessions = SessionStorageImpl::new(
+            "/nonexistent/sessions.toml",
+            &tmux,
+            &RestoreSettings::default(),
+        );
+
+        // When
+        let result = sessions.restore_session("test", &session, &mut Vec::new());
+
+        // Then
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn should_pass_inherited_environment_to_split_panes() {
+        // Given
+        let variable = |name: &str, value: &str| EnvironmentVariable {
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+        let session = TmuxSession {
+            background: None,
+            no_recent_tracking: None,
+            windows: vec![TmuxWindow {
+                index: 1,
+                name: "main".to_string(),
+                layout: "0000,80x24,0,0{40x24,0,0,1,39x24,41,0,2}".to_string(),
+                panes: vec![
+                    pane(1),
+                    TmuxPane {
+                        environment: vec![variable("EDITOR", "vi")],
+                        ..pane(2)
+                    },
+                ],
+                options: vec![],
+                active: Some(true),
+                environment: vec![variable("STAGE", "dev")],
+            }],
+            options: vec![],
+            environment: vec![variable("EDITOR", "nano"), variable("LANG", "C")],
+            template: None,
+        };
+        let mut tmux = MockTmux::new();
+        tmux.expect_new_session()
+            .withf(|_, _, environment, _| {
+                environment
+                    .iter()
+                    .any(|e| e.name == "EDITOR" && e.value == "nano")
+            })
+            .returning(|_, _, _, _| Ok("%0".to_string()));
+        tmux.expect_split_pane()
+            .withf(|_, options| {
+                let mut names = options
+                    .environment
+                    .iter()
+                    .map(|e| format!("{}={}", e.name, e.value))
+                    .collect::<Vec<_>>();
+                names.sort();
+                names == ["EDITOR=vi", "LANG=C", "STAGE=dev"]
+            })
+            .times(1)
+            .returning(|_, _| Ok("%1".to_string()));
+        tmux.expect_select_pane_by_id().returning(|_| Ok(()));
+        tmux.expect_select_window_by_pane_id().returning(|_| Ok(()));
+        let sessions = SessionStorageImpl::new(
+            "/nonexistent/sessions.toml",
+            &tmux,
+            &RestoreSettings::default(),
+        );
+
+        // When
+        let result = sessions.restore_session("test", &session, &mut Vec::new());
+
+        // Then
+        assert!(result.is_ok());
     }
 }
@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+
+use crate::error::{Error, Result};
+use crate::model::EnvironmentVariable;
+
+const FZF_DEFAULT_OPTS: &str = "--bind=alt-q:close,alt-j:down,alt-k:up,alt-u:page-up,alt-d:page-down,tab:accept --color=fg:#cdd6f4,header:#f38ba8,info:#cba6f7,pointer:#f5e0dc --color=marker:#b4befe,fg+:#cdd6f4,prompt:#cba6f7,hl+:#f38ba8 --color=selected-bg:#45475a";
+
+/// General stmux settings stored in `config.toml`. Every section is optional.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct Settings {
+    /// Set in every pane stmux creates, before session, window and pane variables.
+    pub(crate) environment: Vec<EnvironmentVariable>,
+    /// Command the bookmarks and recent sessions files are edited with, the filename is appended.
+    /// Neovim with stmux's own `nvim-config.lua` if not set.
+    pub(crate) editor: Option<String>,
+    pub(crate) save: SaveSettings,
+    pub(crate) scrollback: ScrollbackSettings,
+    pub(crate) autosave: AutosaveSettings,
+    pub(crate) restore: RestoreSettings,
+    pub(crate) picker: PickerSettings,
+    pub(crate) popup: PopupSettings,
+    pub(crate) projects: ProjectsSettings,
+}
+
+impl Settings {
+    /// Shell command editing the given file.
+    pub(crate) fn editor_command(&self, neovim_config_filename: &str, filename: &str) -> String {
+        match &self.editor {
+            Some(editor) => format!("{} {}", editor, filename),
+            None => format!("nvim --clean -u {} {}", neovim_config_filename, filename),
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            environment: vec![EnvironmentVariable {
+                name: "NO_CD".to_string(),
+                value: "1".to_string(),
+            }],
+            editor: None,
+            save: SaveSettings::default(),
+            scrollback: ScrollbackSettings::default(),
+            autosave: AutosaveSettings::default(),
+            restore: RestoreSettings::default(),
+            picker: PickerSettings::default(),
+            popup: PopupSettings::default(),
+            projects: ProjectsSettings::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub(crate) struct SaveSettings {
+    /// Programs that are recorded as pane startup commands. Empty means any program.
+    pub(crate) allowed_commands: Vec<String>,
+    /// Programs that are never recorded, even if they are allowed.
+    pub(crate) denied_commands: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct ScrollbackSettings {
+    /// Upper limit of pane contents stored per session.
+    pub(crate) max_bytes_per_session: usize,
+}
+
+impl Default for ScrollbackSettings {
+    fn default() -> Self {
+        Self {
+            max_bytes_per_session: 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct AutosaveSettings {
+    pub(crate) interval_secs: u64,
+    /// Number of snapshots kept in the snapshots directory.
+    pub(crate) keep: usize,
+}
+
+impl Default for AutosaveSettings {
+    fn default() -> Self {
+        Self {
+            interval_secs: 300,
+            keep: 20,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct RestoreSettings {
+    /// Wait before applying the saved layouts, so that tmux has resized the new windows.
+    pub(crate) layout_delay_millis: u64,
+}
+
+impl Default for RestoreSettings {
+    fn default() -> Self {
+        Self {
+            layout_delay_millis: 300,
+        }
+    }
+}
+
+/// The fzf session picker.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct PickerSettings {
+    /// Used when `FZF_DEFAULT_OPTS` is not set.
+    pub(crate) fzf_options: String,
+    /// Extra `--color` options.
+    pub(crate) colors: Vec<String>,
+    pub(crate) cursor_color: String,
+    /// Number of sessions shown without scrolling.
+    pub(crate) max_height: usize,
+}
+
+impl Default for PickerSettings {
+    fn default() -> Self {
+        Self {
+            fzf_options: FZF_DEFAULT_OPTS.to_string(),
+            colors: [
+                "border:#806aba",
+                "scrollbar:#5c5068",
+                "separator:#5c5068",
+                "label:italic:#9f7fff",
+                "gutter:#1a1323",
+                "current-bg:#3a2943",
+                "marker:#FF0000",
+            ]
+            .map(String::from)
+            .to_vec(),
+            cursor_color: "#a08afa".to_string(),
+            max_height: 5,
+        }
+    }
+}
+
+/// Popups the bookmarks and recent sessions are edited in.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct PopupSettings {
+    pub(crate) style: String,
+    pub(crate) title_style: String,
+}
+
+impl Default for PopupSettings {
+    fn default() -> Self {
+        Self {
+            style: "fg=#806aba".to_string(),
+            title_style: "fg=#9f7fff,italics,align=centre".to_string(),
+        }
+    }
+}
+
+/// Git repositories offered by the `find-all` picker as new sessions.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct ProjectsSettings {
+    /// Directories searched for repositories, `~` is expanded.
+    pub(crate) roots: Vec<String>,
+    /// How many levels below a root repositories are looked for.
+    pub(crate) max_depth: usize,
+    /// Template new project sessions are created from. A single window if not set.
+    pub(crate) template: Option<String>,
+}
+
+impl Default for ProjectsSettings {
+    fn default() -> Self {
+        Self {
+            roots: vec![],
+            max_depth: 2,
+            template: None,
+        }
+    }
+}
+
+pub(crate) trait SettingsFile {
+    fn load(&self) -> Result<Settings>;
+}
+
+pub(crate) struct SettingsFileImpl {
+    filename: String,
+}
+
+impl SettingsFileImpl {
+    pub(crate) fn new(filename: &str) -> Self {
+        Self {
+            filename: filename.to_string(),
+        }
+    }
+}
+
+impl SettingsFile for SettingsFileImpl {
+    fn load(&self) -> Result<Settings> {
+        match fs::read_to_string(&self.filename) {
+            Ok(content) => toml::from_str(&content)
+                .map_err(|error| Error::parse(&self.filename, error.message())),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Settings::default()),
+            Err(error) => Err(Error::io(&self.filename)(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod load_tests {
+    use super::*;
+
+    #[test]
+    fn should_match_the_defaults_with_the_bundled_config() {
+        // When
+        let settings: Settings = toml::from_str(include_str!("../config/config.toml")).unwrap();
+
+        // Then
+        let defaults = Settings::default();
+        assert_eq!(settings.environment, defaults.environment);
+        assert_eq!(settings.editor, defaults.editor);
+        assert_eq!(settings.picker.fzf_options, defaults.picker.fzf_options);
+        assert_eq!(settings.picker.colors, defaults.picker.colors);
+        assert_eq!(settings.popup.style, defaults.popup.style);
+        assert_eq!(
+            settings.restore.layout_delay_millis,
+            defaults.restore.layout_delay_millis
+        );
+    }
+
+    #[test]
+    fn should_keep_the_default_environment_if_not_configured() {
+        // When
+        let settings: Settings = toml::from_str("[restore]\nlayout_delay_millis = 0").unwrap();
+
+        // Then
+        assert_eq!(settings.environment, Settings::default().environment);
+        assert_eq!(settings.restore.layout_delay_millis, 0);
+    }
+}
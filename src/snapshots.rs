@@ -0,0 +1,100 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, Result};
+use crate::state_file;
+
+pub(crate) struct Snapshot {
+    /// Milliseconds since the Unix epoch.
+    pub(crate) timestamp: u128,
+    pub(crate) filename: PathBuf,
+}
+
+pub(crate) trait Snapshots {
+    /// Writes a new timestamped snapshot and returns its filename.
+    fn write(&self, content: &str) -> Result<PathBuf>;
+    /// Lists snapshots, newest first.
+    fn list(&self) -> Vec<Snapshot>;
+    /// Removes all but the newest `keep` snapshots.
+    fn prune(&self, keep: usize);
+}
+
+pub(crate) struct SnapshotsImpl {
+    dirname: PathBuf,
+    prefix: String,
+}
+
+impl SnapshotsImpl {
+    pub(crate) fn new(dirname: &str, prefix: &str) -> Self {
+        Self {
+            dirname: PathBuf::from(dirname),
+            prefix: prefix.to_string(),
+        }
+    }
+
+    fn parse_timestamp(&self, filename: &str) -> Option<u128> {
+        filename
+            .strip_prefix(&self.prefix)?
+            .strip_prefix('-')?
+            .strip_suffix(".toml")?
+            .parse()
+            .ok()
+    }
+}
+
+impl Snapshots for SnapshotsImpl {
+    fn write(&self, content: &str) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dirname).map_err(Error::io(self.dirname.display()))?;
+
+        let mut timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+
+        // Two snapshots within the same millisecond must not overwrite each other.
+        while self
+            .list()
+            .first()
+            .is_some_and(|latest| latest.timestamp >= timestamp)
+        {
+            timestamp += 1;
+        }
+
+        let filename = self
+            .dirname
+            .join(format!("{}-{}.toml", self.prefix, timestamp));
+        state_file::write(&filename.to_string_lossy(), content)?;
+
+        Ok(filename)
+    }
+
+    fn list(&self) -> Vec<Snapshot> {
+        let Ok(entries) = fs::read_dir(&self.dirname) else {
+            return Vec::new();
+        };
+
+        let mut snapshots: Vec<Snapshot> = entries
+            .map_while(std::result::Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name();
+                let timestamp = self.parse_timestamp(name.to_str()?)?;
+
+                Some(Snapshot {
+                    timestamp,
+                    filename: entry.path(),
+                })
+            })
+            .collect();
+
+        snapshots.sort_by_key(|s| Reverse(s.timestamp));
+        snapshots
+    }
+
+    fn prune(&self, keep: usize) {
+        for snapshot in self.list().into_iter().skip(keep) {
+            let _ = fs::remove_file(&snapshot.filename);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::fs::{self, File, OpenOptions};
+use std::path::Path;
+use std::process;
+
+use nix::fcntl::{Flock, FlockArg};
+
+use crate::error::{Error, Result};
+
+/// Takes an exclusive advisory lock for the given state file. The lock is held until the returned
+/// guard is dropped. A separate `.lock` file is used, because the state file itself is replaced on
+/// every write.
+pub(crate) fn lock(filename: &str) -> Result<Flock<File>> {
+    let lock_filename = format!("{}.lock", filename);
+
+    if let Some(parent) = Path::new(&lock_filename).parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_filename)
+        .map_err(Error::io(&lock_filename))?;
+
+    Flock::lock(file, FlockArg::LockExclusive)
+        .map_err(|(_, errno)| Error::io(&lock_filename)(errno.into()))
+}
+
+/// Replaces the file in one step, so readers never see it half written. Does not lock.
+pub(crate) fn write(filename: &str, content: &str) -> Result<()> {
+    let tmp_filename = format!("{}.tmp.{}", filename, process::id());
+
+    fs::write(&tmp_filename, content).map_err(Error::io(&tmp_filename))?;
+    fs::rename(&tmp_filename, filename).map_err(|error| {
+        let _ = fs::remove_file(&tmp_filename);
+        Error::io(filename)(error)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+    use std::thread;
+
+    #[test]
+    fn should_serialize_read_modify_write_sequences() {
+        // Given
+        let directory = TempDir::new();
+        let filename = directory.filename("state");
+        write(&filename, "0").unwrap();
+
+        // When
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let filename = filename.clone();
+                thread::spawn(move || {
+                    for _ in 0..25 {
+                        let _lock = lock(&filename).unwrap();
+                        let value: usize = fs::read_to_string(&filename).unwrap().parse().unwrap();
+                        write(&filename, &(value + 1).to_string()).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        // Then
+        let result = fs::read_to_string(&filename).unwrap();
+        assert_eq!(result, "200");
+    }
+}
@@ -1,23 +1,24 @@
 use crate::{
+    bookmark_file::{BookmarkFile, DEFAULT_GROUP},
+    error::Result,
     model::{StatusPane, StatusWindow},
-    session_name_file::SessionNameFile,
     status_config::StatusConfig,
     tmux::Tmux,
     window::{Window, WindowImpl},
 };
 
 pub(crate) trait Status {
-    fn get(&self) -> String;
-    fn set(&self);
+    fn get(&self) -> Result<String>;
+    fn set(&self) -> Result<()>;
 }
 
-pub(crate) struct StatusImpl<'t, 'b, 'c, T: Tmux, B: SessionNameFile> {
+pub(crate) struct StatusImpl<'t, 'b, 'c, T: Tmux, B: BookmarkFile> {
     tmux: &'t T,
     bookmarks: &'b B,
     config: &'c StatusConfig,
 }
 
-impl<'t, 'b, 'c, T: Tmux, B: SessionNameFile> StatusImpl<'t, 'b, 'c, T, B> {
+impl<'t, 'b, 'c, T: Tmux, B: BookmarkFile> StatusImpl<'t, 'b, 'c, T, B> {
     pub(crate) fn new(tmux: &'t T, bookmarks: &'b B, config: &'c StatusConfig) -> Self {
         Self {
             tmux,
@@ -27,8 +28,8 @@ impl<'t, 'b, 'c, T: Tmux, B: SessionNameFile> StatusImpl<'t, 'b, 'c, T, B> {
     }
 }
 
-impl<'t, 'b, 'c, T: Tmux, B: SessionNameFile> Status for StatusImpl<'t, 'b, 'c, T, B> {
-    fn get(&self) -> String {
+impl<'t, 'b, 'c, T: Tmux, B: BookmarkFile> Status for StatusImpl<'t, 'b, 'c, T, B> {
+    fn get(&self) -> Result<String> {
         fn format_pane(w: &StatusWindow, p: &StatusPane, c: &StatusConfig) -> String {
             let name = if w.panes.len() == 1 {
                 w.name.clone()
@@ -83,16 +84,16 @@ impl<'t, 'b, 'c, T: Tmux, B: SessionNameFile> Status for StatusImpl<'t, 'b, 'c,
             )
         }
 
-        let active_session_names = self.tmux.list_sessions("#S").unwrap();
-        let session_name = self.tmux.current_session_name();
+        let active_session_names = self.tmux.list_sessions("#S")?;
+        let session_name = self.tmux.current_session_name()?;
         let window = WindowImpl::new(self.tmux);
-        let windows = window.list_names_for_status();
-        let bookmarks = self.bookmarks.read();
+        let windows = window.list_names_for_status()?;
+        let (group, bookmarks) = self.bookmarks.read_group()?;
         let bookmark_names = bookmarks
             .iter()
             .enumerate()
-            .map(|(i, v)| {
-                let (color, colon_color) = if active_session_names.contains(v) {
+            .map(|(i, bookmark)| {
+                let (color, colon_color) = if active_session_names.contains(&bookmark.session) {
                     (
                         self.config.colors.active.session_number.as_str(),
                         self.config.colors.active.number_separator.as_str(),
@@ -104,11 +105,11 @@ impl<'t, 'b, 'c, T: Tmux, B: SessionNameFile> Status for StatusImpl<'t, 'b, 'c,
                     )
                 };
 
-                if v == &session_name {
+                if bookmark.session == session_name {
                     let index = if i == 0 {
-                        format!("{}", i + 1)
+                        format!("{}", bookmark.slot)
                     } else {
-                        format!(" {}", i + 1)
+                        format!(" {}", bookmark.slot)
                     };
 
                     format!(
@@ -117,34 +118,161 @@ impl<'t, 'b, 'c, T: Tmux, B: SessionNameFile> Status for StatusImpl<'t, 'b, 'c,
                         index,
                         self.config.colors.selected.number_separator,
                         self.config.style.number_separator,
-                        current(v, &windows, self.config)
+                        current(bookmark.label(), &windows, self.config)
                     )
                 } else {
                     format!(
                         "{}{}{}:{}{}",
                         color,
-                        i + 1,
+                        bookmark.slot,
                         colon_color,
                         self.config.colors.inactive.session_name,
-                        v
+                        bookmark.label()
                     )
                 }
             })
             .collect::<Vec<String>>()
             .join(" ");
 
-        format!(
-            "{}{}",
-            if !bookmarks.contains(&session_name) {
+        Ok(format!(
+            "{}{}{}",
+            if group != DEFAULT_GROUP {
+                format!("{}{}  ", self.config.colors.group_name, group)
+            } else {
+                "".to_string()
+            },
+            if !bookmarks.iter().any(|b| b.session == session_name) {
                 format!("{}  ", current(&session_name, &windows, self.config))
             } else {
                 "".to_string()
             },
             bookmark_names
-        )
+        ))
+    }
+
+    fn set(&self) -> Result<()> {
+        self.tmux.set_global("status-left", &self.get()?)
+    }
+}
+
+#[cfg(test)]
+mod get_tests {
+    use super::*;
+    use crate::{
+        bookmark_file::{Bookmark, MockBookmarkFile},
+        status_config::{ActiveColors, Colors, InactiveColors, SelectedColors, Style},
+        tmux::MockTmux,
+    };
+
+    /// No colors, so that only the text is left.
+    fn status_config() -> StatusConfig {
+        StatusConfig {
+            colors: Colors {
+                group_name: String::new(),
+                inactive: InactiveColors {
+                    session_number: String::new(),
+                    number_separator: String::new(),
+                    session_name: String::new(),
+                },
+                active: ActiveColors {
+                    session_number: String::new(),
+                    number_separator: String::new(),
+                    session_name: String::new(),
+                },
+                selected: SelectedColors {
+                    session_number: String::new(),
+                    number_separator: String::new(),
+                    session_name: String::new(),
+                    window_before: String::new(),
+                    active_pane: String::new(),
+                    pane_separator: String::new(),
+                    inactive_pane: String::new(),
+                    window_after: String::new(),
+                },
+            },
+            style: Style {
+                number_separator: ":".to_string(),
+                window_before: String::new(),
+                pane_separator: String::new(),
+                window_after: String::new(),
+                bookmark_separator: String::new(),
+                selected_bookmark_separator: String::new(),
+            },
+        }
+    }
+
+    fn tmux(session_name: &'static str) -> MockTmux {
+        let mut tmux = MockTmux::new();
+        tmux.expect_list_sessions()
+            .returning(|_| Ok(vec!["main".to_string(), "work".to_string()]));
+        tmux.expect_current_session_name()
+            .returning(move || Ok(session_name.to_string()));
+        tmux.expect_list_current_session_panes()
+            .returning(|_| Ok(vec!["1:1:code:1::1".to_string()]));
+        tmux
+    }
+
+    fn bookmarks(group: &'static str) -> MockBookmarkFile {
+        let mut file = MockBookmarkFile::new();
+        file.expect_read_group().returning(move || {
+            Ok((
+                group.to_string(),
+                vec![
+                    Bookmark {
+                        slot: 1,
+                        session: "main".to_string(),
+                        alias: None,
+                    },
+                    Bookmark {
+                        slot: 3,
+                        session: "work".to_string(),
+                        alias: Some("w".to_string()),
+                    },
+                ],
+            ))
+        });
+        file
+    }
+
+    #[test]
+    fn should_show_the_slots_and_aliases() {
+        // Given
+        let tmux = tmux("notes");
+        let file = bookmarks(DEFAULT_GROUP);
+        let config = status_config();
+
+        // When
+        let result = StatusImpl::new(&tmux, &file, &config).get();
+
+        // Then
+        assert_eq!(result.unwrap(), "notes code  1:main 3:w");
     }
 
-    fn set(&self) {
-        self.tmux.set_global("status-left", &self.get());
+    #[test]
+    fn should_show_the_windows_of_a_bookmarked_current_session() {
+        // Given
+        let tmux = tmux("work");
+        let file = bookmarks(DEFAULT_GROUP);
+        let config = status_config();
+
+        // When
+        let result = StatusImpl::new(&tmux, &file, &config).get();
+
+        // Then
+        assert_eq!(result.unwrap(), "1:main  3:w code ");
+    }
+
+    #[test]
+    fn should_show_a_group_other_than_the_default_one() {
+        // Given
+        let tmux = tmux("notes");
+        let file = bookmarks("on-call");
+        let config = status_config();
+
+        // When
+        let result = StatusImpl::new(&tmux, &file, &config).get();
+
+        // Then
+        assert_eq!(result.unwrap(), "on-call  notes code  1:main 3:w");
     }
 }
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::io::ErrorKind;
-use std::process;
+
+use crate::error::{Error, Result};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub(crate) struct StatusConfig {
@@ -11,6 +11,9 @@ pub(crate) struct StatusConfig {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub(crate) struct Colors {
+    /// The active bookmark group, shown unless it's the default one.
+    #[serde(default)]
+    pub(crate) group_name: String,
     pub(crate) inactive: InactiveColors,
     pub(crate) active: ActiveColors,
     pub(crate) selected: SelectedColors,
@@ -53,7 +56,7 @@ pub(crate) struct Style {
 }
 
 pub(crate) trait StatusConfigFile {
-    fn load(&self) -> StatusConfig;
+    fn load(&self) -> Result<StatusConfig>;
 }
 
 pub(crate) struct StatusConfigFileImpl {
@@ -69,27 +72,9 @@ impl StatusConfigFileImpl {
 }
 
 impl StatusConfigFile for StatusConfigFileImpl {
-    fn load(&self) -> StatusConfig {
-        let file_content = fs::read_to_string(&self.filename);
+    fn load(&self) -> Result<StatusConfig> {
+        let content = fs::read_to_string(&self.filename).map_err(Error::io(&self.filename))?;
 
-        match file_content {
-            Ok(content) => toml::from_str(&content).unwrap_or_else(|error| {
-                panic!("Failed to parse {}: {}.", &self.filename, error.message())
-            }),
-            Err(e) => {
-                match e.kind() {
-                    ErrorKind::NotFound => {
-                        eprintln!("Status config file '{}' does not exist.", &self.filename);
-                    }
-                    _ => {
-                        eprintln!(
-                            "Failed to read status config file '{}': {}",
-                            &self.filename, e
-                        );
-                    }
-                }
-                process::exit(1);
-            }
-        }
+        toml::from_str(&content).map_err(|error| Error::parse(&self.filename, error.message()))
     }
 }
@@ -0,0 +1,214 @@
+use std::{collections::HashMap, fs, io::ErrorKind};
+
+use crate::{
+    error::{Error, Result},
+    model::{TemplateLink, TemplateVariable, TmuxSession},
+};
+
+/// Sessions in `templates.toml` whose strings may contain `${name}` placeholders.
+pub(crate) trait Templates {
+    /// Builds a session from the template, replacing every placeholder. The session is linked to
+    /// the template and the variables it was built with.
+    fn instantiate(
+        &self,
+        template_name: &str,
+        variables: &[TemplateVariable],
+    ) -> Result<TmuxSession>;
+}
+
+pub(crate) struct TemplatesImpl {
+    filename: String,
+}
+
+impl TemplatesImpl {
+    pub(crate) fn new(filename: &str) -> Self {
+        Self {
+            filename: filename.to_string(),
+        }
+    }
+
+    fn load(&self) -> Result<HashMap<String, toml::Value>> {
+        match fs::read_to_string(&self.filename) {
+            Ok(content) => toml::from_str(&content)
+                .map_err(|error| Error::parse(&self.filename, error.message())),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(error) => Err(Error::io(&self.filename)(error)),
+        }
+    }
+}
+
+impl Templates for TemplatesImpl {
+    fn instantiate(
+        &self,
+        template_name: &str,
+        variables: &[TemplateVariable],
+    ) -> Result<TmuxSession> {
+        let Some(mut template) = self.load()?.remove(template_name) else {
+            return Err(Error::NotFound(format!(
+                "Template '{}' not found.",
+                template_name
+            )));
+        };
+
+        substitute_all(&mut template, variables).map_err(|name| {
+            Error::parse(
+                &self.filename,
+                format!(
+                    "Unknown variable '{}' in template '{}'.",
+                    name, template_name
+                ),
+            )
+        })?;
+
+        let mut session: TmuxSession = template
+            .try_into()
+            .map_err(|error: toml::de::Error| Error::parse(&self.filename, error.message()))?;
+        session.template = Some(TemplateLink {
+            name: template_name.to_string(),
+            variables: variables.to_vec(),
+        });
+
+        Ok(session)
+    }
+}
+
+/// The `${name}` and `${path}` variables every session created from a template gets.
+pub(crate) fn builtin_variables(name: &str, path: &str) -> Vec<TemplateVariable> {
+    vec![
+        TemplateVariable {
+            name: "name".to_string(),
+            value: name.to_string(),
+        },
+        TemplateVariable {
+            name: "path".to_string(),
+            value: path.to_string(),
+        },
+    ]
+}
+
+/// Parses a `NAME=VALUE` command line argument.
+pub(crate) fn parse_variable(arg: &str) -> std::result::Result<TemplateVariable, String> {
+    let (name, value) = arg
+        .split_once('=')
+        .ok_or(format!("Expected NAME=VALUE, got '{}'.", arg))?;
+
+    Ok(TemplateVariable {
+        name: name.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// Substitutes every string in the value. Fails with the name of an unknown variable.
+fn substitute_all(
+    value: &mut toml::Value,
+    variables: &[TemplateVariable],
+) -> std::result::Result<(), String> {
+    match value {
+        toml::Value::String(text) => *text = substitute(text, variables)?,
+        toml::Value::Array(values) => {
+            for value in values {
+                substitute_all(value, variables)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (_, value) in table.iter_mut() {
+                substitute_all(value, variables)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn substitute(text: &str, variables: &[TemplateVariable]) -> std::result::Result<String, String> {
+    let mut result = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("${") {
+        let Some(length) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + length];
+        let variable = variables
+            .iter()
+            .find(|v| v.name == name)
+            .ok_or(name.to_string())?;
+
+        result.push_str(&rest[..start]);
+        result.push_str(&variable.value);
+        rest = &rest[start + length + 1..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod instantiate_tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+
+    fn variable(name: &str, value: &str) -> TemplateVariable {
+        TemplateVariable {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn templates(directory: &TempDir) -> TemplatesImpl {
+        let filename = directory.filename("templates.toml");
+        fs::write(&filename, TEMPLATE).unwrap();
+        TemplatesImpl::new(&filename)
+    }
+
+    const TEMPLATE: &str = r#"
+[project]
+windows = [
+    { index = 1, name = "${name}", layout = "", options = [], panes = [
+        { index = 1, path = "${path}", active = true, shell_command = "git -C ${path} status" },
+    ] },
+]
+"#;
+
+    #[test]
+    fn should_replace_placeholders_and_link_the_template() {
+        // Given
+        let directory = TempDir::new();
+        let templates = templates(&directory);
+        let variables = vec![variable("name", "api"), variable("path", "/src/api")];
+
+        // When
+        let result = templates.instantiate("project", &variables);
+
+        // Then
+        let session = result.unwrap();
+        let window = &session.windows[0];
+        assert_eq!(window.name, "api");
+        assert_eq!(window.panes[0].path, "/src/api");
+        assert_eq!(
+            window.panes[0].shell_command.as_deref(),
+            Some("git -C /src/api status")
+        );
+        assert_eq!(
+            session.template,
+            Some(TemplateLink {
+                name: "project".to_string(),
+                variables,
+            })
+        );
+    }
+
+    #[test]
+    fn should_fail_on_an_unknown_variable() {
+        // Given
+        let directory = TempDir::new();
+        let templates = templates(&directory);
+
+        // When
+        let result = templates.instantiate("project", &[variable("name", "api")]);
+
+        // Then
+        assert!(matches!(result, Err(Error::Parse { message, .. }) if message.contains("'path'")));
+    }
+}
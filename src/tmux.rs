@@ -1,9 +1,13 @@
-use std::process::Command;
+use std::fmt;
+use std::io;
 use std::process::Stdio;
+use std::process::{Command, ExitStatus, Output};
 
 use mockall::automock;
 
 use crate::command_builder::CommandBuilder;
+use crate::error::{Error, Result};
+use crate::layout;
 use crate::model::EnvironmentVariable;
 use crate::model::Position;
 use crate::model::TmuxOption;
@@ -16,22 +20,26 @@ pub(crate) struct SplitWindowOptions {
     pub(crate) startup_command: Option<String>,
     pub(crate) at_index: Option<usize>,
     pub(crate) before: bool,
+    /// Width or height of the new pane in cells, depending on the split direction.
+    pub(crate) size: Option<usize>,
+    pub(crate) environment: Vec<EnvironmentVariable>,
 }
 
 #[automock]
 pub(crate) trait Tmux {
-    fn list_sessions(&self, format: &str) -> Result<Vec<String>, Vec<String>>;
-    fn list_current_session_panes(&self, format: &str) -> Vec<String>;
-    fn list_session_panes(&self, session_name: &str, format: &str) -> Vec<String>;
-    fn list_current_window_panes(&self, format: &str) -> Vec<String>;
-    fn list_windows_for_current_session(&self, format: &str) -> Vec<String>;
-    // fn list_windows_names_with_status(&self) -> Vec<WindowDetails>;
+    fn list_sessions(&self, format: &str) -> Result<Vec<String>>;
+    fn list_current_session_panes(&self, format: &str) -> Result<Vec<String>>;
+    fn list_session_panes(&self, session_name: &str, format: &str) -> Result<Vec<String>>;
+    fn list_current_window_panes(&self, format: &str) -> Result<Vec<String>>;
+    fn list_windows_for_current_session(&self, format: &str) -> Result<Vec<String>>;
+    // fn list_windows_names_with_status(&self) -> Result<Vec<WindowDetails>>;
     fn new_session(
         &self,
         session_name: &str,
         tmux_window: &TmuxWindow,
+        environment: &[EnvironmentVariable],
         startup_command: &Option<String>,
-    );
+    ) -> Result<String>;
     fn new_window_in_current_session(
         &self,
         window_name: &str,
@@ -39,7 +47,7 @@ pub(crate) trait Tmux {
         environment: &[EnvironmentVariable],
         startup_command: &Option<String>,
         background: bool,
-    );
+    ) -> Result<()>;
     fn new_window(
         &self,
         session_name: &str,
@@ -48,12 +56,17 @@ pub(crate) trait Tmux {
         environment: &[EnvironmentVariable],
         startup_command: &Option<String>,
         background: bool,
-    );
-    fn has_session(&self, session_name: &str) -> bool;
-    fn select_window(&self, session_name: &str, index: usize);
-    fn current_session_name(&self) -> String;
-    fn select_session(&self, session_name: &str);
-    fn display_message(&self, message: &str);
+    ) -> Result<String>;
+    fn has_session(&self, session_name: &str) -> Result<bool>;
+    fn select_window(&self, session_name: &str, index: usize) -> Result<()>;
+    /// Selects the window containing the given pane.
+    fn select_window_by_pane_id(&self, pane_id: &str) -> Result<()>;
+    fn current_session_name(&self) -> Result<String>;
+    fn select_session(&self, session_name: &str) -> Result<()>;
+    fn rename_session(&self, session_name: &str, new_name: &str) -> Result<()>;
+    fn kill_session(&self, session_name: &str) -> Result<()>;
+    fn display_message(&self, message: &str) -> Result<()>;
+    fn refresh_status(&self) -> Result<()>;
     fn display_popup(
         &self,
         title: &str,
@@ -62,22 +75,60 @@ pub(crate) trait Tmux {
         dimension: &WindowDimension,
         y: &Option<usize>,
         command: &str,
-    );
-    fn split_current_window(&self, options: &SplitWindowOptions);
-    fn split_window(&self, session_name: &str, window_name: &str, options: &SplitWindowOptions);
-    fn select_layout(&self, session_name: &str, window_name: &str, layout: &str);
-    fn send_keys_to_current_window(&self, pane_index: usize, keys: &str);
-    fn send_keys(&self, session_name: &str, window_name: &str, pane_index: usize, keys: &str);
-    fn window_dimension(&self) -> Option<WindowDimension>;
-    fn set_global(&self, option_name: &str, value: &str);
-    fn current_window_index(&self) -> usize;
-    fn get_pane_option(&self, pane_index: &str, option_name: &str) -> Option<String>;
-    fn count_panes(&self) -> usize;
-    fn set_pane_option_for_current_window(&self, pane_index: usize, name: &str, value: &str);
-    fn set_pane_option(&self, window_name: &str, pane_index: usize, name: &str, value: &str);
-    fn set_current_window_pane_option(&self, pane_index: usize, name: &str, value: &str);
-    fn set_session_option(&self, session_name: &str, option: &TmuxOption);
-    fn set_window_option(&self, window_name: &str, option: &TmuxOption);
+    ) -> Result<()>;
+    fn split_current_window(&self, options: &SplitWindowOptions) -> Result<()>;
+    /// Splits the given pane and returns the id of the new one.
+    fn split_pane(&self, pane_id: &str, options: &SplitWindowOptions) -> Result<String>;
+    fn select_layout(&self, session_name: &str, window_name: &str, layout: &str) -> Result<()>;
+    fn send_keys_to_current_window(&self, pane_index: usize, keys: &str) -> Result<()>;
+    fn send_keys(
+        &self,
+        session_name: &str,
+        window_name: &str,
+        pane_index: usize,
+        keys: &str,
+    ) -> Result<()>;
+    fn send_keys_to_pane(&self, pane_id: &str, keys: &str) -> Result<()>;
+    fn window_dimension(&self) -> Result<Option<WindowDimension>>;
+    fn set_global(&self, option_name: &str, value: &str) -> Result<()>;
+    fn current_window_index(&self) -> Result<usize>;
+    fn get_pane_option(&self, pane_index: &str, option_name: &str) -> Result<Option<String>>;
+    fn count_panes(&self) -> Result<usize>;
+    fn set_pane_option_for_current_window(
+        &self,
+        pane_index: usize,
+        name: &str,
+        value: &str,
+    ) -> Result<()>;
+    fn set_pane_option(
+        &self,
+        window_name: &str,
+        pane_index: usize,
+        name: &str,
+        value: &str,
+    ) -> Result<()>;
+    fn set_pane_option_by_id(&self, pane_id: &str, name: &str, value: &str) -> Result<()>;
+    #[allow(dead_code)]
+    fn set_current_window_pane_option(
+        &self,
+        pane_index: usize,
+        name: &str,
+        value: &str,
+    ) -> Result<()>;
+    fn set_session_option(&self, session_name: &str, option: &TmuxOption) -> Result<()>;
+    fn set_window_option(&self, window_name: &str, option: &TmuxOption) -> Result<()>;
+    fn show_session_options(&self, session_name: &str) -> Result<Vec<TmuxOption>>;
+    fn show_window_options(
+        &self,
+        session_name: &str,
+        window_index: usize,
+    ) -> Result<Vec<TmuxOption>>;
+    fn capture_pane(
+        &self,
+        session_name: &str,
+        window_index: usize,
+        pane_index: usize,
+    ) -> Result<String>;
 
     fn swap_panes(
         &self,
@@ -85,35 +136,57 @@ pub(crate) trait Tmux {
         source_pane_index: usize,
         target_window_name: &str,
         target_pane_index: usize,
-    );
-    fn rename_window_in_current_session(&self, old_name: &str, new_name: &str);
+    ) -> Result<()>;
+    fn rename_window_in_current_session(&self, old_name: &str, new_name: &str) -> Result<()>;
     fn join_pane_to_current_window(
         &self,
         window_name: &str,
         pane_index: usize,
         at_index: Option<usize>,
         before: bool,
-    );
-    fn select_pane(&self, index: usize);
-    fn get_cursor_position(&self) -> Option<Position>;
-    fn break_pane(&self, pane_index: usize, window_name: Option<String>);
-    fn window_exists(&self, window_name: &str) -> bool;
-    fn get_str(&self, message: &str) -> String;
-    fn get_str_opt(&self, message: &str) -> Option<String>;
-    fn raw<'a>(&self, args: Vec<&'a str>);
-    fn raw_str<'a>(&self, args: Vec<&'a str>) -> String;
-    fn raw_str_opt<'a>(&self, args: Vec<&'a str>) -> Option<String>;
-    fn raw_vec<'a>(&self, args: Vec<&'a str>) -> Vec<String>;
-    fn get_str_raw<'a>(&self, args: Vec<&'a str>) -> String;
+    ) -> Result<()>;
+    fn select_pane(&self, index: usize) -> Result<()>;
+    fn select_pane_by_id(&self, pane_id: &str) -> Result<()>;
+    fn get_cursor_position(&self) -> Result<Option<Position>>;
+    #[allow(dead_code)]
+    fn break_pane(&self, pane_index: usize, window_name: Option<String>) -> Result<()>;
+    #[allow(dead_code)]
+    fn window_exists(&self, window_name: &str) -> Result<bool>;
+    fn get_str(&self, message: &str) -> Result<String>;
+    #[allow(dead_code)]
+    fn get_str_opt(&self, message: &str) -> Result<Option<String>>;
+    #[allow(clippy::needless_lifetimes)] // Required by automock.
+    fn raw<'a>(&self, args: Vec<&'a str>) -> Result<()>;
+    #[allow(dead_code)]
+    #[allow(clippy::needless_lifetimes)] // Required by automock.
+    fn raw_str<'a>(&self, args: Vec<&'a str>) -> Result<String>;
+    #[allow(clippy::needless_lifetimes)] // Required by automock.
+    fn raw_str_opt<'a>(&self, args: Vec<&'a str>) -> Result<Option<String>>;
+    #[allow(clippy::needless_lifetimes)] // Required by automock.
+    fn raw_vec<'a>(&self, args: Vec<&'a str>) -> Result<Vec<String>>;
+    #[allow(dead_code)]
+    #[allow(clippy::needless_lifetimes)] // Required by automock.
+    fn get_str_raw<'a>(&self, args: Vec<&'a str>) -> Result<String>;
 }
 
 pub(crate) struct TmuxImpl<'cb, CB: CommandBuilder> {
     pub command_builder: &'cb CB,
+    /// Set in every new pane, before the pane's own variables.
+    environment: Vec<EnvironmentVariable>,
 }
 
 impl<'cb, CB: CommandBuilder> TmuxImpl<'cb, CB> {
-    pub fn new(command_builder: &'cb CB) -> Self {
-        Self { command_builder }
+    pub fn new(command_builder: &'cb CB, environment: &[EnvironmentVariable]) -> Self {
+        Self {
+            command_builder,
+            environment: environment.to_vec(),
+        }
+    }
+
+    fn environment_args(&self, command: &mut Command, environment: &[EnvironmentVariable]) {
+        for env in self.environment.iter().chain(environment) {
+            command.arg("-e").arg(format!("{}={}", env.name, env.value));
+        }
     }
 
     fn new_window<F>(
@@ -123,37 +196,48 @@ impl<'cb, CB: CommandBuilder> TmuxImpl<'cb, CB> {
         environment: &[EnvironmentVariable],
         startup_command: &Option<String>,
         decorator_fn: F,
-    ) where
+    ) -> Result<String>
+    where
         F: FnOnce(&mut Command),
     {
         let command = &mut self.command_builder.new_command();
-        command.arg("new-window");
+        command
+            .arg("new-window")
+            .arg("-P")
+            .arg("-F")
+            .arg("#{pane_id}");
 
         decorator_fn(command);
 
-        command.arg("-n").arg(window_name).arg("-e").arg("NO_CD=1");
+        command.arg("-n").arg(window_name);
 
         if let Some(path) = path {
             command.arg("-c").arg(path);
         }
 
-        for env in environment.iter() {
-            command.arg("-e").arg(format!("{}={}", env.name, env.value));
-        }
+        self.environment_args(command, environment);
 
         if let Some(program) = startup_command {
             command.arg(program);
         }
 
-        command.status().expect("Failed to create new session.");
+        pane_id(
+            self.command_builder,
+            command,
+            "Failed to create new window.",
+        )
     }
 
-    fn split_window<F>(&self, options: &SplitWindowOptions, decorator_fn: F)
+    fn split_window<F>(&self, options: &SplitWindowOptions, decorator_fn: F) -> Result<String>
     where
         F: FnOnce(&mut Command),
     {
         let command = &mut self.command_builder.new_command();
-        command.arg("split-window");
+        command
+            .arg("split-window")
+            .arg("-P")
+            .arg("-F")
+            .arg("#{pane_id}");
 
         if options.horizontally {
             command.arg("-h");
@@ -171,21 +255,24 @@ impl<'cb, CB: CommandBuilder> TmuxImpl<'cb, CB> {
             command.arg("-b");
         }
 
-        // WTF is this NO_CD=1 doing here?
-        command.arg("-e").arg("NO_CD=1");
+        if let Some(size) = options.size {
+            command.arg("-l").arg(size.to_string());
+        }
 
         if let Some(p) = &options.path {
             command.arg("-c").arg(p);
         }
 
+        self.environment_args(command, &options.environment);
+
         if let Some(program) = &options.startup_command {
             command.arg(program);
         }
 
-        command.status().expect("Failed to split a window.");
+        pane_id(self.command_builder, command, "Failed to split a window.")
     }
 
-    fn send_keys<F>(&self, keys: &str, decorator_fn: F)
+    fn send_keys<F>(&self, keys: &str, decorator_fn: F) -> Result<()>
     where
         F: FnOnce(&mut Command),
     {
@@ -193,14 +280,15 @@ impl<'cb, CB: CommandBuilder> TmuxImpl<'cb, CB> {
         command.arg("send-keys");
         decorator_fn(command);
 
+        command.arg(keys).arg("C-m");
         command
-            .arg(keys)
-            .arg("C-m")
-            .status()
-            .expect("Failed to send keys.");
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to send keys."))?;
+
+        Ok(())
     }
 
-    fn set_pane_option<F>(&self, name: &str, value: &str, decorator_fn: F)
+    fn set_pane_option<F>(&self, name: &str, value: &str, decorator_fn: F) -> Result<()>
     where
         F: FnOnce(&mut Command),
     {
@@ -209,44 +297,38 @@ impl<'cb, CB: CommandBuilder> TmuxImpl<'cb, CB> {
 
         decorator_fn(command);
 
+        command.arg(name).arg(value);
         command
-            .arg(name)
-            .arg(value)
-            .status()
-            .expect("Failed to get the count of window panes.");
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to get the count of window panes."))?;
+
+        Ok(())
     }
 }
 
 impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
-    fn list_sessions(&self, format: &str) -> Result<Vec<String>, Vec<String>> {
+    fn list_sessions(&self, format: &str) -> Result<Vec<String>> {
         let output = &self
             .command_builder
             .new_command()
             .arg("list-sessions")
             .arg("-F")
             .arg(format)
-            .output()
-            .expect("Failed to list tmux sessions.");
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to list tmux sessions."))?;
 
-        let s = if output.status.success() {
-            &output.stdout
-        } else {
-            &output.stderr
-        };
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::Tmux(stderr.trim().to_string()));
+        }
 
-        let result = String::from_utf8_lossy(s)
+        Ok(String::from_utf8_lossy(&output.stdout)
             .lines()
             .map(|s| s.to_string())
-            .collect::<Vec<String>>();
-
-        if output.status.success() {
-            Ok(result)
-        } else {
-            Err(result)
-        }
+            .collect())
     }
 
-    fn list_session_panes(&self, session_name: &str, format: &str) -> Vec<String> {
+    fn list_session_panes(&self, session_name: &str, format: &str) -> Result<Vec<String>> {
         let output = &self
             .command_builder
             .new_command()
@@ -256,14 +338,14 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
             .arg(session_name)
             .arg("-F")
             .arg(format)
-            .output()
-            .expect("Failed to list session panes.");
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to list session panes."))?;
 
         let result = String::from_utf8_lossy(&output.stdout);
-        result.lines().map(|s| s.to_string()).collect()
+        Ok(result.lines().map(|s| s.to_string()).collect())
     }
 
-    fn list_current_session_panes(&self, format: &str) -> Vec<String> {
+    fn list_current_session_panes(&self, format: &str) -> Result<Vec<String>> {
         let output = &self
             .command_builder
             .new_command()
@@ -271,34 +353,35 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
             .arg("-s")
             .arg("-F")
             .arg(format)
-            .output()
-            .expect("Failed to list current session panes.");
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to list current session panes."))?;
 
         let result = String::from_utf8_lossy(&output.stdout);
-        result.lines().map(|x| x.to_string()).collect()
+        Ok(result.lines().map(|x| x.to_string()).collect())
     }
 
-    fn list_current_window_panes(&self, format: &str) -> Vec<String> {
+    fn list_current_window_panes(&self, format: &str) -> Result<Vec<String>> {
         let output = &self
             .command_builder
             .new_command()
             .arg("list-panes")
             .arg("-F")
             .arg(format)
-            .output()
-            .expect("Failed to list current window panes.");
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to list current window panes."))?;
 
         let result = String::from_utf8_lossy(&output.stdout);
 
-        result.lines().map(|x| x.to_string()).collect()
+        Ok(result.lines().map(|x| x.to_string()).collect())
     }
 
     fn new_session(
         &self,
         session_name: &str,
         tmux_window: &TmuxWindow,
+        environment: &[EnvironmentVariable],
         startup_command: &Option<String>,
-    ) {
+    ) -> Result<String> {
         let name = tmux_window.name.as_str();
         let panes = &tmux_window.panes;
 
@@ -311,20 +394,26 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
         command
             .arg("new-session")
             .arg("-d")
+            .arg("-P")
+            .arg("-F")
+            .arg("#{pane_id}")
             .arg("-s")
             .arg(session_name)
             .arg("-n")
-            .arg(name)
-            .arg("-e") // TODO: Add global config first and add global env variables from there.
-            .arg("NO_CD=1");
+            .arg(name);
 
-        if !panes.is_empty() {
-            let pane = &panes[0];
+        // Sized like the saved window so that its panes can be split to their saved sizes.
+        if let Some(layout) = layout::parse(&tmux_window.layout) {
+            command
+                .arg("-x")
+                .arg(layout.width.to_string())
+                .arg("-y")
+                .arg(layout.height.to_string());
+        }
 
-            for env in pane.environment.iter() {
-                command.arg("-e").arg(format!("{}={}", env.name, env.value));
-            }
+        self.environment_args(command, environment);
 
+        if let Some(pane) = panes.first() {
             command.arg("-c").arg(pane.path.as_str());
         }
 
@@ -332,7 +421,11 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
             command.arg(program);
         }
 
-        command.status().expect("Failed to create new session.");
+        pane_id(
+            self.command_builder,
+            command,
+            "Failed to create new session.",
+        )
     }
 
     fn new_window_in_current_session(
@@ -342,14 +435,16 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
         environment: &[EnvironmentVariable],
         startup_command: &Option<String>,
         background: bool,
-    ) {
+    ) -> Result<()> {
         let decorator = |command: &mut Command| {
             if background {
                 command.arg("-d");
             }
         };
 
-        self.new_window(window_name, path, environment, startup_command, decorator);
+        self.new_window(window_name, path, environment, startup_command, decorator)?;
+
+        Ok(())
     }
 
     fn new_window(
@@ -360,7 +455,7 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
         environment: &[EnvironmentVariable],
         startup_command: &Option<String>,
         background: bool,
-    ) {
+    ) -> Result<String> {
         let decorator = |command: &mut Command| {
             command.arg("-t").arg(session_name);
 
@@ -369,10 +464,10 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
             }
         };
 
-        self.new_window(window_name, path, environment, startup_command, decorator);
+        self.new_window(window_name, path, environment, startup_command, decorator)
     }
 
-    fn has_session(&self, session_name: &str) -> bool {
+    fn has_session(&self, session_name: &str) -> Result<bool> {
         let output = &self
             .command_builder
             .new_command()
@@ -381,53 +476,120 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
             .arg(format!("={}", session_name))
             .stdout(Stdio::null())
             .stderr(Stdio::null())
-            .status()
-            .expect("Failed to check if session exists.");
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to check if session exists."))?;
 
-        output.success()
+        Ok(output.success())
     }
 
-    fn select_window(&self, session_name: &str, index: usize) {
+    fn select_window(&self, session_name: &str, index: usize) -> Result<()> {
         self.command_builder
             .new_command()
             .arg("select-window")
             .arg("-t")
             .arg(format!("{}:{}", session_name, index))
-            .status()
-            .expect("Failed to select window.");
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to select window."))?;
+
+        Ok(())
+    }
+
+    fn select_window_by_pane_id(&self, pane_id: &str) -> Result<()> {
+        let command = &mut self.command_builder.new_command();
+        command.arg("select-window").arg("-t").arg(pane_id);
+        command
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to select window."))?;
+
+        Ok(())
     }
 
-    fn current_session_name(&self) -> String {
+    fn current_session_name(&self) -> Result<String> {
         let stdout = &self
             .command_builder
             .new_command()
             .arg("display-message")
             .arg("-p")
             .arg("#S")
-            .output()
-            .expect("Failed to get current session name.")
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to get current session name."))?
             .stdout;
 
-        String::from_utf8_lossy(stdout).trim().to_string()
+        Ok(String::from_utf8_lossy(stdout).trim().to_string())
     }
 
-    fn select_session(&self, session_name: &str) {
+    fn select_session(&self, session_name: &str) -> Result<()> {
         self.command_builder
             .new_command()
             .arg("switch-client")
             .arg("-t")
             .arg(session_name)
-            .status()
-            .expect("Failed to select session.");
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to select session."))?;
+
+        Ok(())
     }
 
-    fn display_message(&self, message: &str) {
+    fn rename_session(&self, session_name: &str, new_name: &str) -> Result<()> {
+        let status = self
+            .command_builder
+            .new_command()
+            .arg("rename-session")
+            .arg("-t")
+            .arg(format!("={}", session_name))
+            .arg(new_name)
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to rename session."))?;
+
+        if !status.success() {
+            return Err(Error::Tmux(format!(
+                "Failed to rename session '{}'.",
+                session_name
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn kill_session(&self, session_name: &str) -> Result<()> {
+        let status = self
+            .command_builder
+            .new_command()
+            .arg("kill-session")
+            .arg("-t")
+            .arg(format!("={}", session_name))
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to kill session."))?;
+
+        if !status.success() {
+            return Err(Error::Tmux(format!(
+                "Failed to kill session '{}'.",
+                session_name
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn display_message(&self, message: &str) -> Result<()> {
         self.command_builder
             .new_command()
             .arg("display-message")
             .arg(message)
-            .status()
-            .expect("Failed to display message.");
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to display message."))?;
+
+        Ok(())
+    }
+
+    // A temporary workaround for when hooks are not implemented in tmux yet.
+    fn refresh_status(&self) -> Result<()> {
+        Command::new("stmux")
+            .arg("status")
+            .status_with(self.command_builder)
+            .map_err(Error::io("stmux"))?;
+
+        Ok(())
     }
 
     fn display_popup(
@@ -438,7 +600,7 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
         dimension: &WindowDimension,
         y: &Option<usize>,
         command: &str,
-    ) {
+    ) -> Result<()> {
         let cmd = &mut self.command_builder.new_command();
         cmd.arg("display-popup")
             .arg("-E")
@@ -462,68 +624,89 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
             cmd.arg("-y").arg(y.to_string());
         }
 
-        cmd.arg(command).status().expect("Failed to display popup.");
+        cmd.arg(command)
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to display popup."))?;
+
+        Ok(())
     }
 
-    fn split_current_window(&self, options: &SplitWindowOptions) {
-        self.split_window(options, |_| {});
+    fn split_current_window(&self, options: &SplitWindowOptions) -> Result<()> {
+        self.split_window(options, |_| {})?;
+
+        Ok(())
     }
 
-    fn split_window(&self, session_name: &str, window_name: &str, options: &SplitWindowOptions) {
+    fn split_pane(&self, pane_id: &str, options: &SplitWindowOptions) -> Result<String> {
         let decorator = |command: &mut Command| {
-            command
-                .arg("-t")
-                .arg(format!("{}:{}", session_name, window_name));
+            command.arg("-t").arg(pane_id);
         };
 
-        self.split_window(options, decorator);
+        self.split_window(options, decorator)
     }
 
-    fn select_layout(&self, session_name: &str, window_name: &str, layout: &str) {
+    fn select_layout(&self, session_name: &str, window_name: &str, layout: &str) -> Result<()> {
         // eprintln!(
         //     "Selecting layout '{}' for window '{}' in session '{}'.",
         //     layout, window_name, session_name
         // );
-        self.command_builder
-            .new_command()
+        let command = &mut self.command_builder.new_command();
+        command
             .arg("select-layout")
             .arg("-t")
             .arg(format!("{}:{}", session_name, window_name))
-            .arg(layout)
-            .status()
-            .expect("Failed to select window layout.");
+            .arg(layout);
+        command
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to select window layout."))?;
+
+        Ok(())
     }
 
-    fn send_keys_to_current_window(&self, pane_index: usize, keys: &str) {
+    fn send_keys_to_current_window(&self, pane_index: usize, keys: &str) -> Result<()> {
         let decorator = |command: &mut Command| {
             command.arg("-t").arg(format!(".{}", pane_index));
         };
 
-        self.send_keys(keys, decorator);
+        self.send_keys(keys, decorator)
     }
 
-    fn send_keys(&self, session_name: &str, window_name: &str, pane_index: usize, keys: &str) {
+    fn send_keys(
+        &self,
+        session_name: &str,
+        window_name: &str,
+        pane_index: usize,
+        keys: &str,
+    ) -> Result<()> {
         let decorator = |command: &mut Command| {
             command
                 .arg("-t")
                 .arg(format!("{}:{}.{}", session_name, window_name, pane_index));
         };
 
-        self.send_keys(keys, decorator);
+        self.send_keys(keys, decorator)
     }
 
-    fn window_dimension(&self) -> Option<WindowDimension> {
+    fn send_keys_to_pane(&self, pane_id: &str, keys: &str) -> Result<()> {
+        let decorator = |command: &mut Command| {
+            command.arg("-t").arg(pane_id);
+        };
+
+        self.send_keys(keys, decorator)
+    }
+
+    fn window_dimension(&self) -> Result<Option<WindowDimension>> {
         let output = &self
             .command_builder
             .new_command()
             .arg("display-message")
             .arg("-p")
             .arg("#{window_width}x#{window_height}")
-            .output()
-            .expect("Failed to get window dimension.");
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to get window dimension."))?;
 
         let dimension_str = String::from_utf8_lossy(&output.stdout);
-        dimension_str
+        Ok(dimension_str
             .trim()
             .split_once('x')
             .into_iter()
@@ -532,49 +715,53 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
                 let height = height.parse::<usize>().ok()?;
                 Some(WindowDimension { width, height })
             })
-            .next()
+            .next())
     }
 
-    fn set_global(&self, option_name: &str, value: &str) {
+    fn set_global(&self, option_name: &str, value: &str) -> Result<()> {
         self.command_builder
             .new_command()
             .arg("set")
             .arg("-g")
             .arg(option_name)
             .arg(value)
-            .status()
-            .expect("Failed to set global option.");
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to set global option."))?;
+
+        Ok(())
     }
 
-    fn list_windows_for_current_session(&self, format: &str) -> Vec<String> {
+    fn list_windows_for_current_session(&self, format: &str) -> Result<Vec<String>> {
         let output = &self
             .command_builder
             .new_command()
             .arg("list-windows")
             .arg("-F")
             .arg(format)
-            .output()
-            .expect("Failed to list tmux windows.");
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to list tmux windows."))?;
 
         let result = String::from_utf8_lossy(&output.stdout);
-        result.lines().map(|x| x.to_string()).collect()
+        Ok(result.lines().map(|x| x.to_string()).collect())
     }
 
-    fn current_window_index(&self) -> usize {
+    fn current_window_index(&self) -> Result<usize> {
         let output = &self
             .command_builder
             .new_command()
             .arg("display-message")
             .arg("-p")
             .arg("#I")
-            .output()
-            .expect("Failed to get current window index.");
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to get current window index."))?;
 
         let id = String::from_utf8_lossy(&output.stdout);
-        id.trim().parse().expect("Failed to parse window index.")
+        id.trim()
+            .parse()
+            .map_err(tmux_error("Failed to parse window index."))
     }
 
-    fn get_pane_option(&self, pane_index: &str, option_name: &str) -> Option<String> {
+    fn get_pane_option(&self, pane_index: &str, option_name: &str) -> Result<Option<String>> {
         let window_name = &self
             .command_builder
             .new_command()
@@ -584,39 +771,46 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
             .arg("-p")
             .arg("-v")
             .arg(option_name)
-            .output()
-            .expect("Failed to get @window-name");
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to get @window-name"))?;
 
         let result = String::from_utf8_lossy(&window_name.stdout);
         let trimmed = result.trim();
 
-        if trimmed.is_empty() {
+        Ok(if trimmed.is_empty() {
             None
         } else {
             Some(trimmed.to_string())
-        }
+        })
     }
 
-    fn count_panes(&self) -> usize {
+    fn count_panes(&self) -> Result<usize> {
         let output = &self
             .command_builder
             .new_command()
             .arg("display-message")
             .arg("-p")
             .arg("#{window_panes}")
-            .output()
-            .expect("Failed to get the count of window panes.");
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to get the count of window panes."))?;
 
         let id = String::from_utf8_lossy(&output.stdout);
-        id.trim().parse().expect("Failed to parse pane count.")
+        id.trim()
+            .parse()
+            .map_err(tmux_error("Failed to parse pane count."))
     }
 
-    fn set_pane_option_for_current_window(&self, pane_index: usize, name: &str, value: &str) {
+    fn set_pane_option_for_current_window(
+        &self,
+        pane_index: usize,
+        name: &str,
+        value: &str,
+    ) -> Result<()> {
         let decorator = |command: &mut Command| {
             command.arg("-t").arg(format!(".{}", pane_index));
         };
 
-        self.set_pane_option(name, value, decorator);
+        self.set_pane_option(name, value, decorator)
     }
 
     fn swap_panes(
@@ -625,7 +819,7 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
         source_pane_index: usize,
         target_window_name: &str,
         target_pane_index: usize,
-    ) {
+    ) -> Result<()> {
         self.command_builder
             .new_command()
             .arg("swap-pane")
@@ -633,19 +827,23 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
             .arg(format!("{}.{}", source_window_name, source_pane_index))
             .arg("-t")
             .arg(format!("{}.{}", target_window_name, target_pane_index))
-            .status()
-            .expect("Failed to swap panes.");
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to swap panes."))?;
+
+        Ok(())
     }
 
-    fn rename_window_in_current_session(&self, old_name: &str, new_name: &str) {
+    fn rename_window_in_current_session(&self, old_name: &str, new_name: &str) -> Result<()> {
         self.command_builder
             .new_command()
             .arg("rename-window")
             .arg("-t")
             .arg(old_name)
             .arg(new_name)
-            .status()
-            .expect("Failed to rename window.");
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to rename window."))?;
+
+        Ok(())
     }
 
     fn join_pane_to_current_window(
@@ -654,7 +852,7 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
         pane_index: usize,
         at_index: Option<usize>,
         before: bool,
-    ) {
+    ) -> Result<()> {
         let command = &mut self.command_builder.new_command();
         command
             .arg("join-pane")
@@ -672,51 +870,87 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
         }
 
         command
-            .status()
-            .expect("Failed to join pane to current window.");
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to join pane to current window."))?;
+
+        Ok(())
     }
 
-    fn select_pane(&self, index: usize) {
+    fn select_pane(&self, index: usize) -> Result<()> {
         self.command_builder
             .new_command()
             .arg("select-pane")
             .arg("-t")
             .arg(format!("{}", index))
-            .status()
-            .expect("Failed to select pane.");
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to select pane."))?;
+
+        Ok(())
     }
 
-    fn set_pane_option(&self, window_name: &str, pane_index: usize, name: &str, value: &str) {
+    fn select_pane_by_id(&self, pane_id: &str) -> Result<()> {
+        let command = &mut self.command_builder.new_command();
+        command.arg("select-pane").arg("-t").arg(pane_id);
+        command
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to select pane."))?;
+
+        Ok(())
+    }
+
+    fn set_pane_option(
+        &self,
+        window_name: &str,
+        pane_index: usize,
+        name: &str,
+        value: &str,
+    ) -> Result<()> {
         let decorator = |command: &mut Command| {
             command
                 .arg("-t")
                 .arg(format!("{}.{}", window_name, pane_index));
         };
 
-        self.set_pane_option(name, value, decorator);
+        self.set_pane_option(name, value, decorator)
     }
 
-    fn set_current_window_pane_option(&self, pane_index: usize, name: &str, value: &str) {
+    fn set_pane_option_by_id(&self, pane_id: &str, name: &str, value: &str) -> Result<()> {
+        let decorator = |command: &mut Command| {
+            command.arg("-t").arg(pane_id);
+        };
+
+        self.set_pane_option(name, value, decorator)
+    }
+
+    fn set_current_window_pane_option(
+        &self,
+        pane_index: usize,
+        name: &str,
+        value: &str,
+    ) -> Result<()> {
         let decorator = |command: &mut Command| {
             command.arg("-t").arg(format!(".{}", pane_index));
         };
 
-        self.set_pane_option(name, value, decorator);
+        self.set_pane_option(name, value, decorator)
     }
 
-    fn set_session_option(&self, session_name: &str, option: &TmuxOption) {
+    fn set_session_option(&self, session_name: &str, option: &TmuxOption) -> Result<()> {
         let command = &mut self.command_builder.new_command();
         command
             .arg("set")
             .arg("-t")
             .arg(session_name)
             .arg(&option.name)
-            .arg(&option.value)
-            .status()
-            .expect("Failed to set session option.");
+            .arg(&option.value);
+        command
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to set session option."))?;
+
+        Ok(())
     }
 
-    fn set_window_option(&self, window_name: &str, option: &TmuxOption) {
+    fn set_window_option(&self, window_name: &str, option: &TmuxOption) -> Result<()> {
         let command = &mut self.command_builder.new_command();
         command
             .arg("set")
@@ -724,33 +958,92 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
             .arg("-t")
             .arg(format!(":{}", window_name))
             .arg(&option.name)
-            .arg(&option.value)
-            .status()
-            .expect("Failed to set session option.");
+            .arg(&option.value);
+        command
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to set session option."))?;
+
+        Ok(())
+    }
+
+    fn show_session_options(&self, session_name: &str) -> Result<Vec<TmuxOption>> {
+        let output = &self
+            .command_builder
+            .new_command()
+            .arg("show-options")
+            .arg("-t")
+            .arg(session_name)
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to show session options."))?;
+
+        let result = String::from_utf8_lossy(&output.stdout);
+        Ok(result.lines().filter_map(parse_option).collect())
+    }
+
+    fn show_window_options(
+        &self,
+        session_name: &str,
+        window_index: usize,
+    ) -> Result<Vec<TmuxOption>> {
+        let output = &self
+            .command_builder
+            .new_command()
+            .arg("show-options")
+            .arg("-w")
+            .arg("-t")
+            .arg(format!("{}:{}", session_name, window_index))
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to show window options."))?;
+
+        let result = String::from_utf8_lossy(&output.stdout);
+        Ok(result.lines().filter_map(parse_option).collect())
     }
 
-    fn get_cursor_position(&self) -> Option<Position> {
+    fn capture_pane(
+        &self,
+        session_name: &str,
+        window_index: usize,
+        pane_index: usize,
+    ) -> Result<String> {
+        let output = &self
+            .command_builder
+            .new_command()
+            .arg("capture-pane")
+            .arg("-p")
+            .arg("-e")
+            .arg("-J")
+            .arg("-S")
+            .arg("-")
+            .arg("-t")
+            .arg(format!("{}:{}.{}", session_name, window_index, pane_index))
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to capture pane."))?;
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    fn get_cursor_position(&self) -> Result<Option<Position>> {
         let output = &self
             .command_builder
             .new_command()
             .arg("display-message")
             .arg("-p")
             .arg("#{cursor_x},#{cursor_y}")
-            .output()
-            .expect("Failed to get cursor position.");
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to get cursor position."))?;
 
         let position_str = String::from_utf8_lossy(&output.stdout);
-        position_str
+        Ok(position_str
             .trim()
             .split_once(',')
             .and_then(|(x_str, y_str)| {
                 let x = x_str.parse::<i32>().ok()?;
                 let y = y_str.parse::<i32>().ok()?;
                 Some(Position { x, y })
-            })
+            }))
     }
 
-    fn break_pane(&self, pane_index: usize, window_name: Option<String>) {
+    fn break_pane(&self, pane_index: usize, window_name: Option<String>) -> Result<()> {
         let command = &mut self.command_builder.new_command();
 
         command
@@ -764,92 +1057,104 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
             command.arg("-n").arg(name);
         }
 
-        command.status().expect("Failed to break pane.");
+        command
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to break pane."))?;
+
+        Ok(())
     }
 
-    fn window_exists(&self, window_name: &str) -> bool {
+    fn window_exists(&self, window_name: &str) -> Result<bool> {
         let output = &self
             .command_builder
             .new_command()
             .arg("list-windows")
             .arg("-F")
             .arg("#{window_name}")
-            .output()
-            .expect("Failed to list windows.");
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to list windows."))?;
 
         let result = String::from_utf8_lossy(&output.stdout);
-        result.lines().any(|line| line == window_name)
+        Ok(result.lines().any(|line| line == window_name))
     }
 
-    fn get_str(&self, message: &str) -> String {
+    fn get_str(&self, message: &str) -> Result<String> {
         let output = &self
             .command_builder
             .new_command()
             .arg("display-message")
             .arg("-p")
             .arg(message)
-            .output()
-            .expect("Failed to display message.");
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to display message."))?;
 
         let result = String::from_utf8_lossy(&output.stdout);
-        result.trim().to_string()
+        Ok(result.trim().to_string())
     }
 
-    fn get_str_opt(&self, message: &str) -> Option<String> {
+    fn get_str_opt(&self, message: &str) -> Result<Option<String>> {
         let output = &self
             .command_builder
             .new_command()
             .arg("display-message")
             .arg("-p")
             .arg(message)
-            .output()
-            .expect("Failed to display message.");
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to display message."))?;
 
         let stdout = String::from_utf8_lossy(&output.stdout);
         let result = stdout.trim().to_string();
 
-        if result.is_empty() {
+        Ok(if result.is_empty() {
             None
         } else {
             Some(result)
-        }
+        })
     }
 
-    fn raw(&self, args: Vec<&str>) {
+    fn raw(&self, args: Vec<&str>) -> Result<()> {
         let command = &mut self.command_builder.new_command();
 
         for arg in args {
             command.arg(arg);
         }
 
-        command.status().expect("Failed to run command.");
+        command
+            .status_with(self.command_builder)
+            .map_err(tmux_error("Failed to run command."))?;
+
+        Ok(())
     }
 
-    fn raw_str(&self, args: Vec<&str>) -> String {
+    fn raw_str(&self, args: Vec<&str>) -> Result<String> {
         let command = &mut self.command_builder.new_command();
 
         for arg in args {
             command.arg(arg);
         }
 
-        let output = command.output().expect("Failed to run command.");
+        let output = command
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to run command."))?;
         let result = String::from_utf8_lossy(&output.stdout);
-        result.trim().to_string()
+        Ok(result.trim().to_string())
     }
 
-    fn raw_vec(&self, args: Vec<&str>) -> Vec<String> {
+    fn raw_vec(&self, args: Vec<&str>) -> Result<Vec<String>> {
         let command = &mut self.command_builder.new_command();
 
         for arg in args {
             command.arg(arg);
         }
 
-        let output = command.output().expect("Failed to run command.");
+        let output = command
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to run command."))?;
         let result = String::from_utf8_lossy(&output.stdout);
-        result.lines().map(|x| x.to_string()).collect()
+        Ok(result.lines().map(|x| x.to_string()).collect())
     }
 
-    fn get_str_raw(&self, args: Vec<&str>) -> String {
+    fn get_str_raw(&self, args: Vec<&str>) -> Result<String> {
         let command = &mut self.command_builder.new_command();
 
         command.arg("display-message").arg("-p");
@@ -858,30 +1163,97 @@ impl<'cb, CB: CommandBuilder> Tmux for TmuxImpl<'cb, CB> {
             command.arg(arg);
         }
 
-        let output = command.output().expect("Failed to run command.");
+        let output = command
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to run command."))?;
         let result = String::from_utf8_lossy(&output.stdout);
-        result.trim().to_string()
+        Ok(result.trim().to_string())
     }
 
-    fn raw_str_opt(&self, args: Vec<&str>) -> Option<String> {
+    fn raw_str_opt(&self, args: Vec<&str>) -> Result<Option<String>> {
         let command = &mut self.command_builder.new_command();
 
         for arg in args {
             command.arg(arg);
         }
 
-        let output = command.output().expect("Failed to run command.");
+        let output = command
+            .output_with(self.command_builder)
+            .map_err(tmux_error("Failed to run command."))?;
         let text = String::from_utf8_lossy(&output.stdout);
         let result = text.trim().to_string();
 
-        if result.is_empty() {
+        Ok(if result.is_empty() {
             None
         } else {
             Some(result)
-        }
+        })
     }
 }
 
+fn tmux_error<E: fmt::Display>(message: &str) -> impl FnOnce(E) -> Error {
+    let message = message.to_string();
+    move |error| Error::Tmux(format!("{} ({})", message.trim_end_matches('.'), error))
+}
+
+/// Runs commands through a `CommandBuilder`, which may record them instead.
+trait RunWith {
+    fn status_with(&mut self, command_builder: &impl CommandBuilder) -> io::Result<ExitStatus>;
+    fn output_with(&mut self, command_builder: &impl CommandBuilder) -> io::Result<Output>;
+}
+
+impl RunWith for Command {
+    fn status_with(&mut self, command_builder: &impl CommandBuilder) -> io::Result<ExitStatus> {
+        command_builder.status(self)
+    }
+
+    fn output_with(&mut self, command_builder: &impl CommandBuilder) -> io::Result<Output> {
+        command_builder.output(self)
+    }
+}
+
+/// Runs a command printing `#{pane_id}` of the pane it creates and returns that id.
+fn pane_id(
+    command_builder: &impl CommandBuilder,
+    command: &mut Command,
+    message: &str,
+) -> Result<String> {
+    let output = command
+        .output_with(command_builder)
+        .map_err(tmux_error(message))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Tmux(format!(
+            "{} ({})",
+            message.trim_end_matches('.'),
+            stderr.trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Parses a single `show-options` line, e.g. `status-left "a \"b\" c"`.
+fn parse_option(line: &str) -> Option<TmuxOption> {
+    let (name, value) = line.split_once(' ')?;
+    let value = if value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')))
+    {
+        value[1..value.len() - 1]
+            .replace("\\\"", "\"")
+            .replace("\\\\", "\\")
+    } else {
+        value.to_string()
+    };
+
+    Some(TmuxOption {
+        name: name.to_string(),
+        value,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use crate::command_builder::TestCommandBuilderImpl;
@@ -891,15 +1263,25 @@ mod tests {
     #[test]
     fn test_should_error_while_listing_sessions_on_a_non_running_server() {
         let cb = TestCommandBuilderImpl::new("princesskenny");
-        let tmux = TmuxImpl::new(&cb);
+        let tmux = TmuxImpl::new(&cb, &[]);
         let result = tmux.list_sessions("#W");
         assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(err[0].contains("error connecting"), "Was: {}", err[0]);
-        assert!(
-            err[0].contains("No such file or directory"),
-            "Was: {}",
-            err[0]
-        );
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("error connecting"), "Was: {}", err);
+        assert!(err.contains("No such file or directory"), "Was: {}", err);
+    }
+
+    #[test]
+    fn should_parse_quoted_option_values() {
+        let option = parse_option(r#"status-left "a \"b\" c""#).unwrap();
+        assert_eq!(option.name, "status-left");
+        assert_eq!(option.value, r#"a "b" c"#);
+    }
+
+    #[test]
+    fn should_parse_plain_option_values() {
+        let option = parse_option("@side right").unwrap();
+        assert_eq!(option.name, "@side");
+        assert_eq!(option.value, "right");
     }
 }
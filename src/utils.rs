@@ -1,13 +1,17 @@
 use std::collections::HashMap;
-use std::process::Command;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::model::TmuxSessions;
+use crate::model::{EnvironmentVariable, SessionName, TmuxOption, TmuxSession, TmuxSessions};
 
 pub(crate) fn is_numeric(as_str: &str) -> bool {
     as_str.chars().all(char::is_numeric)
 }
 
+/// Replaces the characters tmux doesn't allow in session names, the same way tmux would.
+pub(crate) fn valid_session_name(name: &str) -> SessionName {
+    name.replace(['.', ':'], "_")
+}
+
 pub(crate) fn merge(config_sessions: TmuxSessions, current_sessions: TmuxSessions) -> TmuxSessions {
     let mut sessions = HashMap::new();
 
@@ -24,6 +28,165 @@ pub(crate) fn merge(config_sessions: TmuxSessions, current_sessions: TmuxSession
     sessions
 }
 
+/// Merges a freshly captured session into its stored counterpart. The layout comes from the
+/// live session, while hand-written fields (`background`, `no_recent_tracking`, environment and
+/// pane commands) are kept from the stored one.
+pub(crate) fn merge_session(stored: Option<TmuxSession>, captured: TmuxSession) -> TmuxSession {
+    let Some(stored) = stored else {
+        return captured;
+    };
+
+    let windows = captured
+        .windows
+        .into_iter()
+        .map(|mut window| {
+            let stored_window = stored
+                .windows
+                .iter()
+                .find(|w| w.name == window.name)
+                .or_else(|| stored.windows.iter().find(|w| w.index == window.index));
+
+            if let Some(stored_window) = stored_window {
+                window.options = merge_options(&stored_window.options, window.options);
+                window.environment = stored_window.environment.clone();
+
+                for pane in window.panes.iter_mut() {
+                    if let Some(stored_pane) =
+                        stored_window.panes.iter().find(|p| p.index == pane.index)
+                    {
+                        // Hand-written commands win over whatever is running right now.
+                        if stored_pane.startup_command.is_some()
+                            || stored_pane.shell_command.is_some()
+                        {
+                            pane.startup_command = stored_pane.startup_command.clone();
+                            pane.shell_command = stored_pane.shell_command.clone();
+                        }
+
+                        if pane.environment.is_empty() {
+                            pane.environment = stored_pane.environment.clone();
+                        }
+
+                        if pane.name.is_none() {
+                            pane.name = stored_pane.name.clone();
+                        }
+                    }
+                }
+            }
+
+            window
+        })
+        .collect();
+
+    TmuxSession {
+        background: stored.background,
+        no_recent_tracking: stored.no_recent_tracking,
+        windows,
+        options: merge_options(&stored.options, captured.options),
+        environment: stored.environment,
+        template: stored.template,
+    }
+}
+
+/// Inherited variables, overridden by the ones with the same name set lower down.
+pub(crate) fn inherit_environment(
+    inherited: &[EnvironmentVariable],
+    own: &[EnvironmentVariable],
+) -> Vec<EnvironmentVariable> {
+    merge_options(inherited, own.to_vec())
+}
+
+/// Stored options are kept unless the live session has a value for the same option.
+fn merge_options(stored: &[TmuxOption], captured: Vec<TmuxOption>) -> Vec<TmuxOption> {
+    let mut options: Vec<TmuxOption> = stored
+        .iter()
+        .filter(|o| !captured.iter().any(|c| c.name == o.name))
+        .cloned()
+        .collect();
+
+    options.extend(captured);
+    options
+}
+
+/// Replaces the `from` directory at the start of pane paths, e.g. for another checkout.
+#[derive(Debug, Clone)]
+pub(crate) struct PathRewrite {
+    pub(crate) from: String,
+    pub(crate) to: String,
+}
+
+/// Parses a `FROM=TO` command line argument.
+pub(crate) fn parse_path_rewrite(arg: &str) -> std::result::Result<PathRewrite, String> {
+    let (from, to) = arg
+        .split_once('=')
+        .filter(|(from, _)| !from.is_empty())
+        .ok_or(format!("Expected FROM=TO, got '{}'.", arg))?;
+
+    Ok(PathRewrite {
+        from: from.trim_end_matches('/').to_string(),
+        to: to.trim_end_matches('/').to_string(),
+    })
+}
+
+/// Applies the first matching rewrite to every pane path. Only whole directory names match, so
+/// `/src/api` doesn't rewrite `/src/api-v2`.
+pub(crate) fn rewrite_paths(session: &mut TmuxSession, rewrites: &[PathRewrite]) {
+    for pane in session
+        .windows
+        .iter_mut()
+        .flat_map(|window| window.panes.iter_mut())
+    {
+        if let Some(rewritten) = rewrites.iter().find_map(|rewrite| {
+            pane.path
+                .strip_prefix(&rewrite.from)
+                .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+                .map(|rest| format!("{}{}", rewrite.to, rest))
+        }) {
+            pane.path = rewritten;
+        }
+    }
+}
+
+/// Quotes an argument for a POSIX shell, leaving simple words untouched.
+pub(crate) fn shell_quote(arg: &str) -> String {
+    let is_plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./=:,+@%".contains(c));
+
+    if is_plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Formats milliseconds since the Unix epoch as `YYYY-MM-DD HH:MM:SS` in UTC.
+pub(crate) fn format_timestamp(millis: u128) -> String {
+    let secs = (millis / 1000) as i64;
+    let (days, time) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+    // Civil from days, see https://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
 pub(crate) fn random_window_name() -> String {
     let nanos = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -33,10 +196,189 @@ pub(crate) fn random_window_name() -> String {
     format!("win_{}", random_number)
 }
 
-// A temporary workaround for when hooks are not implemented in tmux yet.
-pub(crate) fn refresh_status() {
-    Command::new("stmux")
-        .arg("status")
-        .status()
-        .expect("Couldn't refresh status.");
+#[cfg(test)]
+mod format_timestamp_tests {
+    use super::*;
+
+    #[test]
+    fn should_format_timestamps_in_utc() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00");
+        assert_eq!(format_timestamp(951_782_400_000), "2000-02-29 00:00:00");
+        assert_eq!(format_timestamp(1_792_238_875_941), "2026-10-17 12:07:55");
+    }
+}
+
+#[cfg(test)]
+mod rewrite_paths_tests {
+    use super::*;
+    use crate::model::{TmuxPane, TmuxWindow};
+
+    #[test]
+    fn should_rewrite_whole_directories_only() {
+        // Given
+        let pane = |index: usize, path: &str| TmuxPane {
+            index,
+            path: path.to_string(),
+            active: index == 1,
+            startup_command: None,
+            shell_command: None,
+            environment: vec![],
+            name: None,
+        };
+        let mut session = TmuxSession {
+            background: None,
+            no_recent_tracking: None,
+            windows: vec![TmuxWindow {
+                index: 1,
+                name: "code".to_string(),
+                layout: "layout".to_string(),
+                panes: vec![
+                    pane(1, "/src/api"),
+                    pane(2, "/src/api/web"),
+                    pane(3, "/src/api-v2"),
+                ],
+                options: vec![],
+                active: Some(true),
+                environment: vec![],
+            }],
+            options: vec![],
+            environment: vec![],
+            template: None,
+        };
+        let rewrites = vec![parse_path_rewrite("/src/api/=/src/api-fix").unwrap()];
+
+        // When
+        rewrite_paths(&mut session, &rewrites);
+
+        // Then
+        let paths = session.windows[0]
+            .panes
+            .iter()
+            .map(|pane| pane.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec!["/src/api-fix", "/src/api-fix/web", "/src/api-v2"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod merge_session_tests {
+    use super::*;
+    use crate::model::{TmuxPane, TmuxWindow};
+
+    fn pane(index: usize, path: &str) -> TmuxPane {
+        TmuxPane {
+            index,
+            path: path.to_string(),
+            active: index == 1,
+            startup_command: None,
+            shell_command: None,
+            environment: vec![],
+            name: None,
+        }
+    }
+
+    fn session(panes: Vec<TmuxPane>) -> TmuxSession {
+        TmuxSession {
+            background: None,
+            no_recent_tracking: None,
+            windows: vec![TmuxWindow {
+                index: 1,
+                name: "main".to_string(),
+                layout: "layout".to_string(),
+                panes,
+                options: vec![],
+                active: Some(true),
+                environment: vec![],
+            }],
+            options: vec![],
+            environment: vec![],
+            template: None,
+        }
+    }
+
+    #[test]
+    fn should_return_the_captured_session_if_nothing_is_stored() {
+        // Given
+        let captured = session(vec![pane(1, "/tmp")]);
+
+        // When
+        let result = merge_session(None, captured);
+
+        // Then
+        assert_eq!(result.windows[0].panes[0].path, "/tmp");
+    }
+
+    #[test]
+    fn should_keep_hand_written_fields() {
+        // Given
+        let mut stored = session(vec![pane(1, "/old"), pane(2, "/old")]);
+        stored.background = Some(true);
+        stored.windows[0].panes[1].startup_command = Some("nvim".to_string());
+        let captured = session(vec![pane(1, "/new"), pane(2, "/new"), pane(3, "/new")]);
+
+        // When
+        let result = merge_session(Some(stored), captured);
+
+        // Then
+        assert_eq!(result.background, Some(true));
+        assert_eq!(result.windows[0].panes.len(), 3);
+        assert_eq!(result.windows[0].panes[1].path, "/new");
+        assert_eq!(
+            result.windows[0].panes[1].startup_command.as_deref(),
+            Some("nvim")
+        );
+        assert_eq!(result.windows[0].panes[2].startup_command, None);
+    }
+
+    #[test]
+    fn should_not_record_a_running_command_over_a_shell_command() {
+        // Given
+        let mut stored = session(vec![pane(1, "/tmp")]);
+        stored.windows[0].panes[0].shell_command = Some("nvim .".to_string());
+        let mut captured = session(vec![pane(1, "/tmp")]);
+        captured.windows[0].panes[0].startup_command = Some("nvim .".to_string());
+
+        // When
+        let result = merge_session(Some(stored), captured);
+
+        // Then
+        let pane = &result.windows[0].panes[0];
+        assert_eq!(pane.startup_command, None);
+        assert_eq!(pane.shell_command.as_deref(), Some("nvim ."));
+    }
+
+    #[test]
+    fn should_override_stored_options_with_live_ones() {
+        // Given
+        let mut stored = session(vec![pane(1, "/tmp")]);
+        stored.options = vec![
+            TmuxOption {
+                name: "a".to_string(),
+                value: "1".to_string(),
+            },
+            TmuxOption {
+                name: "b".to_string(),
+                value: "1".to_string(),
+            },
+        ];
+        let mut captured = session(vec![pane(1, "/tmp")]);
+        captured.options = vec![TmuxOption {
+            name: "b".to_string(),
+            value: "2".to_string(),
+        }];
+
+        // When
+        let result = merge_session(Some(stored), captured);
+
+        // Then
+        let values: Vec<(&str, &str)> = result
+            .options
+            .iter()
+            .map(|o| (o.name.as_str(), o.value.as_str()))
+            .collect();
+        assert_eq!(values, vec![("a", "1"), ("b", "2")]);
+    }
 }
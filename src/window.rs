@@ -2,18 +2,27 @@ use std::collections::HashMap;
 
 use crate::{
     args::SplitType,
-    model::{StatusPane, StatusWindow, TmuxPane, TmuxSession, TmuxWindow, WindowName},
+    error::Result,
+    model::{
+        EnvironmentVariable, StatusPane, StatusWindow, TmuxPane, TmuxSession, TmuxWindow,
+        WindowName,
+    },
     tmux::{SplitWindowOptions, Tmux},
-    utils::{random_window_name, refresh_status},
+    utils::{self, random_window_name},
 };
 
 pub(crate) trait Window {
     /// Splits the current window into two panes, one for the current session and another for the
     /// first window of given (stored) session.
-    fn smart_split(&self, session_name: &str, session: &TmuxSession, split_type: &SplitType);
-    fn list_with_pane_details(&self, session_name: &str) -> Vec<TmuxWindow>;
-    fn list_names_for_current_session(&self) -> Vec<WindowName>;
-    fn list_names_for_status(&self) -> Vec<StatusWindow>;
+    fn smart_split(
+        &self,
+        session_name: &str,
+        session: &TmuxSession,
+        split_type: &SplitType,
+    ) -> Result<()>;
+    fn list_with_pane_details(&self, session_name: &str) -> Result<Vec<TmuxWindow>>;
+    fn list_names_for_current_session(&self) -> Result<Vec<WindowName>>;
+    fn list_names_for_status(&self) -> Result<Vec<StatusWindow>>;
 }
 
 struct PaneWindowName {
@@ -30,14 +39,23 @@ impl<'t, T: Tmux> WindowImpl<'t, T> {
         Self { tmux }
     }
 
-    fn split_window(&self, pane: &TmuxPane, at_index: Option<usize>, before: bool) -> usize {
+    /// `environment` is the pane's own and inherited variables.
+    fn split_window(
+        &self,
+        pane: &TmuxPane,
+        environment: &[EnvironmentVariable],
+        at_index: Option<usize>,
+        before: bool,
+    ) -> Result<usize> {
         self.tmux.split_current_window(&SplitWindowOptions {
             horizontally: true,
             path: Some(pane.path.clone()),
             startup_command: pane.startup_command.clone(),
             at_index,
             before,
-        });
+            size: None,
+            environment: environment.to_vec(),
+        })?;
 
         let pane_index = if let Some(pane_index) = at_index {
             if before && pane_index == 1 {
@@ -50,7 +68,7 @@ impl<'t, T: Tmux> WindowImpl<'t, T> {
         } else {
             match before {
                 true => 1,
-                false => self.tmux.count_panes(),
+                false => self.tmux.count_panes()?,
             }
         };
 
@@ -58,24 +76,25 @@ impl<'t, T: Tmux> WindowImpl<'t, T> {
             && let Some(shell_command) = &pane.shell_command
         {
             self.tmux
-                .send_keys_to_current_window(pane_index, shell_command);
+                .send_keys_to_current_window(pane_index, shell_command)?;
         }
 
-        pane_index
+        Ok(pane_index)
     }
 
     fn replace_pane(
         &self,
         session_name: &str,
         pane_to_swap: &TmuxPane,
+        environment: &[EnvironmentVariable],
         current_pane: &PaneWindowName,
-    ) {
+    ) -> Result<()> {
         let window_name = current_pane
             .window_name
             .clone()
             .unwrap_or(random_window_name());
 
-        let current_session_window_names = self.list_names_for_current_session();
+        let current_session_window_names = self.list_names_for_current_session()?;
 
         if !current_session_window_names
             .into_iter()
@@ -83,52 +102,54 @@ impl<'t, T: Tmux> WindowImpl<'t, T> {
         {
             if current_pane.index == 1 {
                 self.tmux
-                    .rename_window_in_current_session(&window_name, session_name);
+                    .rename_window_in_current_session(&window_name, session_name)?;
             }
 
             self.tmux.new_window_in_current_session(
                 window_name.as_str(),
                 &Some(pane_to_swap.path.clone()),
-                &pane_to_swap.environment,
+                environment,
                 &pane_to_swap.startup_command,
                 true,
-            );
+            )?;
 
             if pane_to_swap.startup_command.is_none()
                 && let Some(shell_command) = &pane_to_swap.shell_command
             {
-                let current_session_name = self.tmux.current_session_name();
+                let current_session_name = self.tmux.current_session_name()?;
                 self.tmux.send_keys(
                     &current_session_name,
                     &window_name,
                     1,
                     shell_command.as_str(),
-                );
+                )?;
             }
 
-            let current_window = self.tmux.current_window_index().to_string();
+            let current_window = self.tmux.current_window_index()?.to_string();
             self.tmux
-                .swap_panes(&current_window, current_pane.index, window_name.as_str(), 1);
+                .swap_panes(&current_window, current_pane.index, window_name.as_str(), 1)?;
 
             self.tmux
-                .set_pane_option(&window_name, 1, "@window-name", &window_name);
+                .set_pane_option(&window_name, 1, "@window-name", &window_name)?;
         } else {
-            let current_window = self.tmux.current_window_index().to_string();
-            self.tmux.swap_panes(&current_window, current_pane.index, session_name, 1);
+            let current_window = self.tmux.current_window_index()?.to_string();
+            self.tmux
+                .swap_panes(&current_window, current_pane.index, session_name, 1)?;
             self.tmux
-                .rename_window_in_current_session(session_name, window_name.as_str());
+                .rename_window_in_current_session(session_name, window_name.as_str())?;
         }
 
         self.tmux.set_pane_option_for_current_window(
             current_pane.index,
             "@window-name",
             session_name,
-        );
+        )
     }
 
-    fn get_pane_window_names(&self) -> Vec<PaneWindowName> {
-        self.tmux
-            .list_current_window_panes("#{pane_index}:#{@window-name}")
+    fn get_pane_window_names(&self) -> Result<Vec<PaneWindowName>> {
+        Ok(self
+            .tmux
+            .list_current_window_panes("#{pane_index}:#{@window-name}")?
             .iter()
             .map(|pane_info| {
                 let parts = pane_info
@@ -148,7 +169,7 @@ impl<'t, T: Tmux> WindowImpl<'t, T> {
                     },
                 }
             })
-            .collect()
+            .collect())
     }
 }
 
@@ -157,15 +178,24 @@ impl<'t, T: Tmux> Window for WindowImpl<'t, T> {
     /// - Single pane: Creates a split within the current window.
     /// - Multiple panes: Creates a new window using the first pane from the `session`'s first window,
     ///   then swaps it with the current window's last pane.
-    fn smart_split(&self, session_name: &str, session: &TmuxSession, split_type: &SplitType) {
-        if let Some(pane) = session
+    fn smart_split(
+        &self,
+        session_name: &str,
+        session: &TmuxSession,
+        split_type: &SplitType,
+    ) -> Result<()> {
+        if let Some((window, pane)) = session
             .windows
             .first()
-            .and_then(|window| window.panes.first())
+            .and_then(|window| window.panes.first().map(|pane| (window, pane)))
         {
-            let pane_window_names = self.get_pane_window_names();
+            let environment = utils::inherit_environment(
+                &utils::inherit_environment(&session.environment, &window.environment),
+                &pane.environment,
+            );
+            let pane_window_names = self.get_pane_window_names()?;
             let window_exists = {
-                let window_names = self.list_names_for_current_session();
+                let window_names = self.list_names_for_current_session()?;
                 window_names.into_iter().any(|w| w == session_name)
             };
 
@@ -173,30 +203,28 @@ impl<'t, T: Tmux> Window for WindowImpl<'t, T> {
                 match split_type {
                     SplitType::Right => {
                         self.tmux
-                            .join_pane_to_current_window(session_name, 1, None, false)
+                            .join_pane_to_current_window(session_name, 1, None, false)?
                     }
                     SplitType::Left => {
                         self.tmux
-                            .join_pane_to_current_window(session_name, 1, Some(1), true)
+                            .join_pane_to_current_window(session_name, 1, Some(1), true)?
                     }
                 }
 
-                refresh_status();
-                return;
+                return self.tmux.refresh_status();
             }
 
             if pane_window_names.len() == 1 {
                 let pane_index = match split_type {
-                    SplitType::Right => self.split_window(pane, None, false),
-                    SplitType::Left => self.split_window(pane, Some(1), true),
+                    SplitType::Right => self.split_window(pane, &environment, None, false)?,
+                    SplitType::Left => self.split_window(pane, &environment, Some(1), true)?,
                 };
 
-                self.tmux.set_pane_option_for_current_window(
+                return self.tmux.set_pane_option_for_current_window(
                     pane_index,
                     "@window-name",
                     session_name,
                 );
-                return;
             }
 
             if let Some(pane) = pane_window_names
@@ -204,41 +232,61 @@ impl<'t, T: Tmux> Window for WindowImpl<'t, T> {
                 .filter(|p| p.window_name.is_some())
                 .find(|p| matches!(&p.window_name, Some(name) if name == session_name))
             {
-                self.tmux.select_pane(pane.index);
-                return;
+                return self.tmux.select_pane(pane.index);
             }
 
             match split_type {
                 SplitType::Right => {
                     if let Some(current_window_last_pane) = pane_window_names.last() {
-                        self.replace_pane(session_name, pane, current_window_last_pane);
+                        self.replace_pane(
+                            session_name,
+                            pane,
+                            &environment,
+                            current_window_last_pane,
+                        )?;
                     }
                 }
                 SplitType::Left => {
                     if let Some(current_window_first_pane) = pane_window_names.first() {
-                        self.replace_pane(session_name, pane, current_window_first_pane);
+                        self.replace_pane(
+                            session_name,
+                            pane,
+                            &environment,
+                            current_window_first_pane,
+                        )?;
                     }
                 }
             }
         }
+
+        Ok(())
     }
 
-    fn list_with_pane_details(&self, session_name: &str) -> Vec<TmuxWindow> {
-        let output = self.tmux.list_session_panes(session_name,
-            "#{window_index}:#{window_name}:#{window_layout}:#{pane_index}:#{pane_active}:#{pane_current_path}");
+    fn list_with_pane_details(&self, session_name: &str) -> Result<Vec<TmuxWindow>> {
+        // Tab separated, because window names, pane names and paths may contain colons.
+        let output = self.tmux.list_session_panes(
+            session_name,
+            "#{window_index}\t#{window_active}\t#{window_layout}\t#{pane_index}\t#{pane_active}\t#{@window-name}\t#{window_name}\t#{pane_current_path}",
+        )?;
 
         let mut windows: Vec<TmuxWindow> = Vec::new();
-        let mut map: HashMap<WindowName, usize> = HashMap::new();
-        let mut index: usize = 0;
+        let mut map: HashMap<usize, usize> = HashMap::new();
+
+        for line in output {
+            let tokens = line.splitn(8, '\t').collect::<Vec<&str>>();
+
+            if tokens.len() < 8 {
+                continue;
+            }
 
-        for window in output {
-            let tokens = window.split(':').collect::<Vec<&str>>();
             let window_index = tokens[0].parse::<usize>().unwrap();
-            let window_name = tokens[1];
+            let window_active = tokens[1] == "1";
             let layout = tokens[2];
             let pindex = tokens[3].parse::<usize>().unwrap();
             let active = tokens[4] == "1";
-            let path = tokens[5].to_string();
+            let pane_name = (!tokens[5].is_empty()).then(|| tokens[5].to_string());
+            let window_name = tokens[6];
+            let path = tokens[7].to_string();
             let pane = TmuxPane {
                 index: pindex,
                 path,
@@ -246,10 +294,10 @@ impl<'t, T: Tmux> Window for WindowImpl<'t, T> {
                 startup_command: None,
                 shell_command: None,
                 environment: vec![],
-                name: None,
+                name: pane_name,
             };
 
-            if let Some(i) = map.get(window_name) {
+            if let Some(i) = map.get(&window_index) {
                 let window = &mut windows[*i];
                 window.panes.push(pane);
             } else {
@@ -258,28 +306,28 @@ impl<'t, T: Tmux> Window for WindowImpl<'t, T> {
                     name: window_name.to_string(),
                     layout: layout.to_string(),
                     panes: vec![pane],
-                    options: vec![],
-                    active: None,
+                    options: self.tmux.show_window_options(session_name, window_index)?,
+                    active: Some(window_active),
+                    environment: vec![],
                 };
 
+                map.insert(window_index, windows.len());
                 windows.push(window);
-                map.insert(window_name.to_string(), index);
-                index += 1;
             }
         }
 
-        windows
+        Ok(windows)
     }
 
-    fn list_names_for_current_session(&self) -> Vec<WindowName> {
+    fn list_names_for_current_session(&self) -> Result<Vec<WindowName>> {
         self.tmux.list_windows_for_current_session("#W")
     }
 
-    fn list_names_for_status(&self) -> Vec<StatusWindow> {
+    fn list_names_for_status(&self) -> Result<Vec<StatusWindow>> {
         // We need to list panes, not windows, to get all panes in each window
         let lines = self.tmux.list_current_session_panes(
             "#{window_index}:#{window_active}:#W:#{pane_index}:#{@window-name}:#{pane_active}",
-        );
+        )?;
 
         let mut windows: HashMap<usize, StatusWindow> = HashMap::new();
 
@@ -321,6 +369,78 @@ impl<'t, T: Tmux> Window for WindowImpl<'t, T> {
 
         let mut result: Vec<StatusWindow> = windows.into_values().collect();
         result.sort_by_key(|w| w.index);
-        result
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod smart_split_tests {
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::tmux::MockTmux;
+
+    fn variable(name: &str, value: &str) -> EnvironmentVariable {
+        EnvironmentVariable {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn should_pass_inherited_environment_to_the_new_pane() {
+        // Given
+        let session = TmuxSession {
+            background: None,
+            no_recent_tracking: None,
+            windows: vec![TmuxWindow {
+                index: 1,
+                name: "main".to_string(),
+                layout: String::new(),
+                panes: vec![TmuxPane {
+                    index: 1,
+                    path: "/src".to_string(),
+                    active: true,
+                    startup_command: None,
+                    shell_command: None,
+                    environment: vec![variable("EDITOR", "vi")],
+                    name: None,
+                }],
+                options: vec![],
+                active: Some(true),
+                environment: vec![variable("STAGE", "dev")],
+            }],
+            options: vec![],
+            environment: vec![variable("EDITOR", "nano"), variable("LANG", "C")],
+            template: None,
+        };
+        let mut tmux = MockTmux::new();
+        tmux.expect_list_current_window_panes()
+            .returning(|_| Ok(vec!["1:".to_string()]));
+        tmux.expect_list_windows_for_current_session()
+            .returning(|_| Ok(vec!["shell".to_string()]));
+        tmux.expect_split_current_window()
+            .withf(|options| {
+                let mut names = options
+                    .environment
+                    .iter()
+                    .map(|e| format!("{}={}", e.name, e.value))
+                    .collect::<Vec<_>>();
+                names.sort();
+                names == ["EDITOR=vi", "LANG=C", "STAGE=dev"]
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        tmux.expect_count_panes().returning(|| Ok(2));
+        tmux.expect_set_pane_option_for_current_window()
+            .with(eq(2), eq("@window-name"), eq("work"))
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        // When
+        let result = WindowImpl::new(&tmux).smart_split("work", &session, &SplitType::Right);
+
+        // Then
+        assert!(result.is_ok());
     }
 }
@@ -1,3 +1,4 @@
+use crate::error::Result;
 use crate::tmux::{SplitWindowOptions, Tmux};
 
 pub(crate) struct WorkflowImpl<'t, T: Tmux> {
@@ -20,21 +21,23 @@ impl<'t, T: Tmux> WorkflowImpl<'t, T> {
         Self { tmux }
     }
 
-    fn list_numeric_windows(&self) -> Vec<usize> {
-        self.tmux
-            .list_windows_for_current_session("#W")
+    fn list_numeric_windows(&self) -> Result<Vec<usize>> {
+        Ok(self
+            .tmux
+            .list_windows_for_current_session("#W")?
             .into_iter()
             .filter_map(|window| window.parse::<usize>().ok())
-            .collect()
+            .collect())
     }
 
     fn parse_names(&self, names_str: &str) -> Vec<String> {
         names_str.split(' ').map(|s| s.trim().to_string()).collect()
     }
 
-    fn get_windows_with_sides(&self) -> Vec<NameAndSide> {
-        self.tmux
-            .raw_vec(vec!["list-windows", "-F", "#W:#{@side}"])
+    fn get_windows_with_sides(&self) -> Result<Vec<NameAndSide>> {
+        Ok(self
+            .tmux
+            .raw_vec(vec!["list-windows", "-F", "#W:#{@side}"])?
             .into_iter()
             .filter_map(|entry| {
                 let mut parts = entry.splitn(2, ':');
@@ -49,12 +52,12 @@ impl<'t, T: Tmux> WorkflowImpl<'t, T> {
                     None
                 }
             })
-            .collect()
+            .collect())
     }
 
-    fn find_next_right_window_name(&self) -> Option<String> {
-        let current_window_name = self.tmux.get_str("#W");
-        let windows = self.get_windows_with_sides();
+    fn find_next_right_window_name(&self) -> Result<Option<String>> {
+        let current_window_name = self.tmux.get_str("#W")?;
+        let windows = self.get_windows_with_sides()?;
         let mut current = false;
 
         for entry in windows {
@@ -62,66 +65,68 @@ impl<'t, T: Tmux> WorkflowImpl<'t, T> {
             if entry.name == current_window_name {
                 current = true;
             } else if current && entry.side == "right" {
-                return Some(entry.name);
+                return Ok(Some(entry.name));
             }
         }
 
-        None
+        Ok(None)
     }
 
-    fn find_next_right_name(&self) -> Option<String> {
-        let mut windows = self.tmux.raw_vec(vec!["list-windows", "-F", "#W"]);
+    fn find_next_right_name(&self) -> Result<Option<String>> {
+        let mut windows = self.tmux.raw_vec(vec!["list-windows", "-F", "#W"])?;
         let right_pane_name =
             self.tmux
-                .raw_str_opt(vec!["display-message", "-p", "-t:.2", "#{@window-name}"]);
+                .raw_str_opt(vec!["display-message", "-p", "-t:.2", "#{@window-name}"])?;
         if let Some(right_name) = right_pane_name {
             windows.push(right_name);
         }
 
-        let names_str = self.tmux.get_str("#{@window-names-right}");
+        let names_str = self.tmux.get_str("#{@window-names-right}")?;
         let names = self.parse_names(names_str.as_str());
 
         if names.is_empty() {
-            return None;
+            return Ok(None);
         }
 
         for name in &names {
             if !windows.contains(name) {
-                return Some(name.clone());
+                return Ok(Some(name.clone()));
             }
         }
 
-        None
+        Ok(None)
     }
 
-    fn find_next_numeric_name(&self) -> String {
-        let windows = &mut self.list_numeric_windows();
+    fn find_next_numeric_name(&self) -> Result<String> {
+        let windows = &mut self.list_numeric_windows()?;
         windows.sort();
         let max = windows.last();
 
         let result = if let Some(value) = max { value + 1 } else { 1 };
-        format!("{}", result)
+        Ok(format!("{}", result))
     }
 
-    fn find_next_alpha_name(&self) -> String {
-        let windows = self.tmux.raw_vec(vec!["list-windows", "-F", "#W"]);
+    #[allow(dead_code)]
+    fn find_next_alpha_name(&self) -> Result<String> {
+        let windows = self.tmux.raw_vec(vec!["list-windows", "-F", "#W"])?;
 
         for c in b'a'..=b'z' {
             let name = (c as char).to_string();
 
             if !windows.contains(&name) {
-                return name;
+                return Ok(name);
             }
         }
 
-        String::from("a")
+        Ok(String::from("a"))
     }
 
-    fn find_candidates(&self, side: &str) -> Vec<Candidate> {
-        let current_index = self.tmux.get_str("#I");
+    fn find_candidates(&self, side: &str) -> Result<Vec<Candidate>> {
+        let current_index = self.tmux.get_str("#I")?;
 
-        self.tmux
-            .raw_vec(vec!["list-windows", "-F", "#I:#W:#{window_panes}:#{@side}"])
+        Ok(self
+            .tmux
+            .raw_vec(vec!["list-windows", "-F", "#I:#W:#{window_panes}:#{@side}"])?
             .into_iter()
             .filter_map(|entry| {
                 let mut parts = entry.splitn(4, ':');
@@ -139,15 +144,17 @@ impl<'t, T: Tmux> WorkflowImpl<'t, T> {
                     None
                 }
             })
-            .collect()
+            .collect())
     }
 
-    fn list_alpha_windows(&self) -> Vec<String> {
-        self.tmux
-            .list_windows_for_current_session("#W")
+    #[allow(dead_code)]
+    fn list_alpha_windows(&self) -> Result<Vec<String>> {
+        Ok(self
+            .tmux
+            .list_windows_for_current_session("#W")?
             .into_iter()
             .filter(|window| !window.chars().all(char::is_numeric))
-            .collect()
+            .collect())
     }
 
     fn cycle_candidate(current: &str, list: &[Candidate], forward: bool) -> Option<Candidate> {
@@ -168,22 +175,22 @@ impl<'t, T: Tmux> WorkflowImpl<'t, T> {
         Some(list[next_pos].clone())
     }
 
-    fn swap_active_pane(&self, forward: bool) {
-        let count = self.tmux.count_panes();
+    fn swap_active_pane(&self, forward: bool) -> Result<()> {
+        let count = self.tmux.count_panes()?;
         if count == 1 {
-            return;
+            return Ok(());
         }
 
-        let pane_left = self.tmux.get_str("#{pane_left}");
+        let pane_left = self.tmux.get_str("#{pane_left}")?;
         let is_leftmost = pane_left == "0";
 
         if is_leftmost {
             eprintln!("chuj");
             let current_name = self
                 .tmux
-                .get_pane_option("1", "@window-name")
+                .get_pane_option("1", "@window-name")?
                 .unwrap_or_default();
-            let mut candidates = self.find_candidates("left");
+            let mut candidates = self.find_candidates("left")?;
             candidates.sort_by_key(|s| s.index.parse::<usize>().unwrap_or(0));
             candidates
                 .iter()
@@ -196,28 +203,28 @@ impl<'t, T: Tmux> WorkflowImpl<'t, T> {
                     ":.1",
                     "-t",
                     &format!("{}.1", target.index),
-                ]);
+                ])?;
                 self.tmux
-                    .set_pane_option_for_current_window(1, "@window-name", &target.name);
-                self.tmux.raw(vec!["rename-window", &target.name]);
+                    .set_pane_option_for_current_window(1, "@window-name", &target.name)?;
+                self.tmux.raw(vec!["rename-window", &target.name])?;
                 self.tmux.raw(vec![
                     "rename-window",
                     "-t",
                     &format!("{}.1", target.index),
                     &current_name,
-                ]);
+                ])?;
             }
 
-            return;
+            return Ok(());
         }
 
         let current = self
             .tmux
-            .get_pane_option("2", "@window-name")
+            .get_pane_option("2", "@window-name")?
             .unwrap_or_default();
 
         eprintln!("window-name: {}", current);
-        let candidates = self.find_candidates("right");
+        let candidates = self.find_candidates("right")?;
         candidates
             .iter()
             .for_each(|c| eprintln!("Candidate: {}", c.name));
@@ -236,13 +243,13 @@ impl<'t, T: Tmux> WorkflowImpl<'t, T> {
 
         if let Some(c) = candidate {
             self.tmux
-                .raw(vec!["swap-pane", "-t", format!("{}.1", c.index).as_str()]);
+                .raw(vec!["swap-pane", "-t", format!("{}.1", c.index).as_str()])?;
             self.tmux.raw(vec![
                 "rename-window",
                 "-t",
                 c.index.as_str(),
                 current.as_str(),
-            ]);
+            ])?;
 
             if let Some(t) = target {
                 self.tmux.raw(vec![
@@ -253,24 +260,26 @@ impl<'t, T: Tmux> WorkflowImpl<'t, T> {
                     ";",
                     "move-window",
                     "-r",
-                ]);
+                ])?;
             }
         }
+
+        Ok(())
     }
 
-    fn swap_rightmost_pane(&self, forward: bool) {
-        let count = self.tmux.count_panes();
+    fn swap_rightmost_pane(&self, forward: bool) -> Result<()> {
+        let count = self.tmux.count_panes()?;
         if count == 1 {
-            return;
+            return Ok(());
         }
 
         let current = self
             .tmux
-            .get_pane_option("{right}", "@window-name")
+            .get_pane_option("{right}", "@window-name")?
             .unwrap_or_default();
 
         eprintln!("window-name: {}", current);
-        let candidates = self.find_candidates("right");
+        let candidates = self.find_candidates("right")?;
         candidates
             .iter()
             .for_each(|c| eprintln!("Candidate: {}", c.name));
@@ -288,14 +297,19 @@ impl<'t, T: Tmux> WorkflowImpl<'t, T> {
         };
 
         if let Some(c) = candidate {
-            self.tmux
-                .raw(vec!["swap-pane", "-s", "{right}", "-t", format!("{}.1", c.index).as_str()]);
+            self.tmux.raw(vec![
+                "swap-pane",
+                "-s",
+                "{right}",
+                "-t",
+                format!("{}.1", c.index).as_str(),
+            ])?;
             self.tmux.raw(vec![
                 "rename-window",
                 "-t",
                 c.index.as_str(),
                 current.as_str(),
-            ]);
+            ])?;
 
             if let Some(t) = target {
                 self.tmux.raw(vec![
@@ -306,69 +320,76 @@ impl<'t, T: Tmux> WorkflowImpl<'t, T> {
                     ";",
                     "move-window",
                     "-r",
-                ]);
+                ])?;
             }
         }
+
+        Ok(())
     }
     // Aka: select previous window binding
-    fn previous(&self) {
-        self.swap_active_pane(false);
+    fn previous(&self) -> Result<()> {
+        self.swap_active_pane(false)
     }
 
     // Aka: select next window binding
-    fn next(&self) {
-        self.swap_active_pane(true);
+    fn next(&self) -> Result<()> {
+        self.swap_active_pane(true)
     }
 
-    fn swap_pane(&self, swap_window_name: &str) {
-        let current_window_index = self.tmux.get_str("#{window_index}");
+    #[allow(dead_code)]
+    fn swap_pane(&self, swap_window_name: &str) -> Result<()> {
+        let current_window_index = self.tmux.get_str("#{window_index}")?;
         let this_index = format!("{}.2", current_window_index);
         let this_window_name = self
             .tmux
-            .get_pane_option(this_index.as_str(), "@window-name")
+            .get_pane_option(this_index.as_str(), "@window-name")?
             .unwrap_or("chuj".to_string());
         let that_index = format!("{}.1", swap_window_name);
         let that_window_name = self
             .tmux
-            .get_pane_option(that_index.as_str(), "@window-name")
+            .get_pane_option(that_index.as_str(), "@window-name")?
             .unwrap_or("chuj".to_string());
         self.tmux
-            .swap_panes(&current_window_index, 2, swap_window_name, 1);
+            .swap_panes(&current_window_index, 2, swap_window_name, 1)?;
         self.tmux.set_pane_option(
             swap_window_name,
             1,
             "@window-name",
             this_window_name.as_str(),
-        );
+        )?;
         self.tmux.set_pane_option(
             current_window_index.as_str(),
             2,
             "@window-name",
             that_window_name.as_str(),
-        );
+        )?;
         self.tmux
-            .rename_window_in_current_session(swap_window_name, this_window_name.as_str());
+            .rename_window_in_current_session(swap_window_name, this_window_name.as_str())?;
+
+        Ok(())
     }
 
-    fn new_left(&self) {
-        let count = self.tmux.count_panes();
+    fn new_left(&self) -> Result<()> {
+        let count = self.tmux.count_panes()?;
 
         if count == 1 {
-            let name = self.find_next_numeric_name();
-            self.tmux.raw(vec!["new-window", "-b", "-n", name.as_str()]);
-            self.tmux.raw(vec!["set-option", "-p", "@side", "left"]);
+            let name = self.find_next_numeric_name()?;
             self.tmux
-                .raw(vec!["set-option", "-p", "@window-name", name.as_str()]);
-            return;
+                .raw(vec!["new-window", "-b", "-n", name.as_str()])?;
+            self.tmux.raw(vec!["set-option", "-p", "@side", "left"])?;
+            self.tmux
+                .raw(vec!["set-option", "-p", "@window-name", name.as_str()])?;
+            return Ok(());
         }
 
-        let old_name = self.tmux.get_pane_option("1", "@window-name");
-        let name = self.find_next_numeric_name();
+        let old_name = self.tmux.get_pane_option("1", "@window-name")?;
+        let name = self.find_next_numeric_name()?;
+        self.tmux
+            .raw(vec!["new-window", "-d", "-a", "-n", name.as_str()])?;
         self.tmux
-            .raw(vec!["new-window", "-d", "-a", "-n", name.as_str()]);
-        self.tmux.raw(vec!["swap-pane", "-s", ":.1", "-t", ":+1.1"]);
+            .raw(vec!["swap-pane", "-s", ":.1", "-t", ":+1.1"])?;
         self.tmux
-            .raw(vec!["set-option", "-pt", ":+1.1", "@side", "left"]);
+            .raw(vec!["set-option", "-pt", ":+1.1", "@side", "left"])?;
         if let Some(ref old) = old_name {
             self.tmux.raw(vec![
                 "set-option",
@@ -376,66 +397,76 @@ impl<'t, T: Tmux> WorkflowImpl<'t, T> {
                 ":+1.1",
                 "@window-name",
                 old.as_str(),
-            ]);
-            self.tmux.raw(vec!["rename-window", "-t:+1", old.as_str()]);
+            ])?;
+            self.tmux
+                .raw(vec!["rename-window", "-t:+1", old.as_str()])?;
         }
-        let new_pane_name = self.find_next_numeric_name();
+        let new_pane_name = self.find_next_numeric_name()?;
         self.tmux
-            .set_pane_option_for_current_window(1, "@window-name", new_pane_name.as_str());
+            .set_pane_option_for_current_window(1, "@window-name", new_pane_name.as_str())?;
         self.tmux
-            .set_pane_option_for_current_window(1, "@side", "left");
+            .set_pane_option_for_current_window(1, "@side", "left")?;
+
+        Ok(())
     }
 
-    fn new_pane(&self) {
-        let path = self.tmux.get_str("#{pane_current_path}");
-        // self.tmux.raw(vec!["display-message", format!("Current path: {}", path).as_str()]);
+    fn new_pane(&self) -> Result<()> {
+        let path = self.tmux.get_str("#{pane_current_path}")?;
+        // self.tmux.raw(vec!["display-message", format!("Current path: {}", path).as_str()])?;
         let options = SplitWindowOptions {
             horizontally: true,
             path: Some(path),
             startup_command: None,
             at_index: None,
             before: false,
+            size: None,
+            environment: vec![],
         };
 
-        let name = self.find_next_right_name();
+        let name = self.find_next_right_name()?;
 
         if let Some(name) = name {
-            self.tmux.split_current_window(&options);
+            self.tmux.split_current_window(&options)?;
 
             self.tmux
-                .set_pane_option_for_current_window(2, "@window-name", name.as_str());
+                .set_pane_option_for_current_window(2, "@window-name", name.as_str())?;
             self.tmux
-                .set_pane_option_for_current_window(2, "@side", "right");
+                .set_pane_option_for_current_window(2, "@side", "right")?;
         }
+
+        Ok(())
     }
 
-    fn break_pane(&self) {
-        let name = self.tmux.get_pane_option("2", "@window-name");
+    fn break_pane(&self) -> Result<()> {
+        let name = self.tmux.get_pane_option("2", "@window-name")?;
 
-        self.tmux.raw(vec!["break-pane", "-s:.2", "-ad"]);
+        self.tmux.raw(vec!["break-pane", "-s:.2", "-ad"])?;
 
         if let Some(name) = name {
-            self.tmux.raw(vec!["rename-window", "-t:+1", name.as_str()]);
+            self.tmux
+                .raw(vec!["rename-window", "-t:+1", name.as_str()])?;
         }
+
+        Ok(())
     }
 
-    fn new_right(&self) {
-        let count = self.tmux.count_panes();
+    fn new_right(&self) -> Result<()> {
+        let count = self.tmux.count_panes()?;
 
         if count == 1 {
-            self.new_pane();
-            return;
+            self.new_pane()?;
+            return Ok(());
         }
 
-        let old_name = self.tmux.get_pane_option("2", "@window-name");
-        let name_opt = self.find_next_right_name();
+        let old_name = self.tmux.get_pane_option("2", "@window-name")?;
+        let name_opt = self.find_next_right_name()?;
         if name_opt.is_none() {
-            return;
+            return Ok(());
         }
 
         let name = name_opt.unwrap();
 
-        let path = self.tmux.get_str("#{pane_current_path}");
+        let path = self.tmux.get_str("#{pane_current_path}")?;
         self.tmux.raw(vec![
             "new-window",
             "-d",
@@ -444,10 +475,11 @@ impl<'t, T: Tmux> WorkflowImpl<'t, T> {
             path.as_str(),
             "-n",
             name.as_str(),
-        ]);
-        self.tmux.raw(vec!["swap-pane", "-s", ":.2", "-t", ":+1.1"]);
+        ])?;
+        self.tmux
+            .raw(vec!["swap-pane", "-s", ":.2", "-t", ":+1.1"])?;
         self.tmux
-            .raw(vec!["set-option", "-pt", ":+1.1", "@side", "right"]);
+            .raw(vec!["set-option", "-pt", ":+1.1", "@side", "right"])?;
         if let Some(ref old) = old_name {
             self.tmux.raw(vec![
                 "set-option",
@@ -455,59 +487,71 @@ impl<'t, T: Tmux> WorkflowImpl<'t, T> {
                 ":+1.1",
                 "@window-name",
                 old.as_str(),
-            ]);
-            self.tmux.raw(vec!["rename-window", "-t:+1", old.as_str()]);
+            ])?;
+            self.tmux
+                .raw(vec!["rename-window", "-t:+1", old.as_str()])?;
         }
         self.tmux
-            .set_pane_option_for_current_window(2, "@window-name", name.as_str());
+            .set_pane_option_for_current_window(2, "@window-name", name.as_str())?;
         self.tmux
-            .set_pane_option_for_current_window(2, "@side", "right");
+            .set_pane_option_for_current_window(2, "@side", "right")?;
+
+        Ok(())
     }
 
-    fn shrink_right_split_or_break(&self) {
+    fn shrink_right_split_or_break(&self) -> Result<()> {
         eprintln!("Executing workflow for key: ]");
+
+        Ok(())
     }
 
-    fn expand_right_split_or_join(&self) {
+    fn expand_right_split_or_join(&self) -> Result<()> {
         eprintln!("Executing workflow for key: [");
+
+        Ok(())
     }
 
-    fn test(&self) {
-        let name = self.find_next_right_name();
+    #[allow(dead_code)]
+    fn test(&self) -> Result<()> {
+        let name = self.find_next_right_name()?;
 
         if let Some(name) = name {
             eprintln!("Next right name: {}", name);
         } else {
             eprintln!("No next right name found");
         }
+
+        Ok(())
     }
 
-    fn toggle(&self) {
-        let pane_count = self.tmux.count_panes();
+    fn toggle(&self) -> Result<()> {
+        let pane_count = self.tmux.count_panes()?;
         let side = self
             .tmux
-            .raw_str_opt(vec!["show-option", "-t", "{right}", "-p", "@side"]);
+            .raw_str_opt(vec!["show-option", "-t", "{right}", "-p", "@side"])?;
         // let next_window_name = self
         //     .tmux
         //     .raw_str_opt(vec!["display-message", "-p", "-t:+", "#W"]);
         if pane_count == 1
-            && let Some(name) = self.find_next_right_window_name()
+            && let Some(name) = self.find_next_right_window_name()?
         {
             self.tmux
-                .join_pane_to_current_window(name.as_str(), 1, None, false);
-            self.next();
+                .join_pane_to_current_window(name.as_str(), 1, None, false)?;
+            self.next()?;
         } else if pane_count == 1 {
-            self.new_right();
-        } else if side.is_none() && self.find_next_right_window_name().is_some() {
-            self.swap_rightmost_pane(true);
-        } else if side.is_none() && self.find_next_right_window_name().is_none() {
-            self.new_right();
+            self.new_right()?;
+        } else if side.is_none() && self.find_next_right_window_name()?.is_some() {
+            self.swap_rightmost_pane(true)?;
+        } else if side.is_none() && self.find_next_right_window_name()?.is_none() {
+            self.new_right()?;
         } else {
-            self.break_pane();
+            self.break_pane()?;
         }
+
+        Ok(())
     }
 
-    pub fn on_action(&self, key: &str) {
+    pub fn on_action(&self, key: &str) -> Result<()> {
         match key {
             "1" => self.previous(),
             "2" => self.next(),
@@ -516,7 +560,10 @@ impl<'t, T: Tmux> WorkflowImpl<'t, T> {
             "w" => self.toggle(),
             "]" => self.shrink_right_split_or_break(),
             "[" => self.expand_right_split_or_join(),
-            _ => eprintln!("No workflow defined for key: {}", key),
+            _ => {
+                eprintln!("No workflow defined for key: {}", key);
+                Ok(())
+            }
         }
     }
 }